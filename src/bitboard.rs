@@ -49,6 +49,9 @@ impl core::ops::Sub for BitBoard {
     }
 }
 
+// b1, d1, ..., a2, c2, ..., h8
+pub const LIGHT_SQUARES: u64 = 0x55aa55aa55aa55aa;
+
 impl BitBoard {
     pub const EMPTY: Self = Self(0);
     pub const FULL: Self = Self(!0);
@@ -1,42 +1,145 @@
+use std::fmt;
 use std::str::FromStr;
 
-use crate::moves::{between_rays, Move};
+use crate::fen::FenError;
+use crate::moves::{between_rays, GenMode, Move, MoveList};
 use crate::pin::PinMask;
+use crate::sliding_pieces::{bishop_attacks, queen_attacks, rook_attacks};
+use crate::zobrist::ZOBRIST;
 use crate::{bitboard::BitBoard, color::Color, gamestate::GameState, pieces::Pieces, PieceType};
-use crate::{BISHOP_LOOKUP, BLACK_PAWN_LOOKUP, KNIGHT_LOOKUP, ROOK_LOOKUP, WHITE_PAWN_LOOKUP};
+use crate::{
+    BISHOP_LOOKUP, BLACK_PAWN_LOOKUP, KING_LOOKUP, KNIGHT_LOOKUP, ROOK_LOOKUP, WHITE_PAWN_LOOKUP,
+};
 
+// everything make_move destroys that unmake_move cannot work out from the move itself
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Undo {
+    pub captured: Option<PieceType>,
+    pub state: GameState,
+    pub halfmoves: usize,
+    pub hash: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Board {
     pub pieces: Pieces,
     pub side_to_move: Color,
     pub halfmoves: usize,
     pub fullmoves: usize,
     pub state: GameState,
+    pub chess960: bool,
+    pub hash: u64,
+    // the pawns alone, for the pawn structure cache
+    pub pawn_hash: u64,
+    // hashes of the positions before each move made on this board, oldest first
+    pub history: Vec<u64>,
 }
-pub struct ParseFenError;
 impl FromStr for Board {
-    type Err = ParseFenError;
+    type Err = FenError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let pieces = s.parse::<Pieces>().ok().unwrap();
-        let state = s.parse::<GameState>().ok().unwrap();
-        let parsed: Vec<&str> = s.split(" ").collect();
-        let side_to_move = match parsed[1].to_lowercase().as_str() {
-            "w" => Color::White,
-            "b" => Color::Black,
-            _ => {
-                panic!("invalid color")
+        let parsed: Vec<&str> = s.split_whitespace().collect();
+        if parsed.len() > 6 {
+            return Err(FenError::TooManyFields);
+        }
+        let pieces = s.parse::<Pieces>()?;
+        let side_to_move = match parsed.get(1) {
+            Some(&"w") => Color::White,
+            Some(&"b") => Color::Black,
+            Some(side) => return Err(FenError::BadSide(side.to_string())),
+            None => return Err(FenError::MissingField("side to move")),
+        };
+        let state = s.parse::<GameState>()?;
+        if let Some(en_pessant) = state.en_pessant {
+            // the pawn that skipped over it belongs to the side that just moved
+            let rank = match side_to_move {
+                Color::White => 5,
+                Color::Black => 2,
+            };
+            // with the pawn that moved in front of it and nothing else in the way
+            let (pawn, behind) = match side_to_move {
+                Color::White => (en_pessant - 8, en_pessant + 8),
+                Color::Black => (en_pessant + 8, en_pessant - 8),
+            };
+            let theirs = match side_to_move {
+                Color::White => !pieces.white.0,
+                Color::Black => pieces.white.0,
+            };
+            let occupied = pieces.king.0
+                | pieces.queen.0
+                | pieces.rook.0
+                | pieces.bishop.0
+                | pieces.knight.0
+                | pieces.pawn.0;
+            if en_pessant >> 3 != rank
+                || pieces.pawn.0 & theirs & 1 << pawn == 0
+                || occupied & (1 << en_pessant | 1 << behind) != 0
+            {
+                return Err(FenError::BadEnPassant(parsed[3].to_owned()));
             }
+        }
+        // the counters are often left off, assume a fresh game
+        let counter = |index: usize, default: usize| match parsed.get(index) {
+            Some(counter) => counter
+                .parse::<usize>()
+                .map_err(|_| FenError::BadCounter(counter.to_string())),
+            None => Ok(default),
         };
-        let halfmoves = parsed[4].chars().next().unwrap() as usize - 48; // '0'
-        let fullmoves = parsed[5].chars().next().unwrap() as usize - 48;
+        let halfmoves = counter(4, 0)?;
+        let fullmoves = counter(5, 1)?;
+        if fullmoves == 0 {
+            return Err(FenError::BadCounter(parsed[5].to_owned()));
+        }
+
+        // anything but rooks in the corners and the king on e1 or e8 needs chess960 castling
+        let (queenside, kingside, black_queenside, black_kingside) = state.castling;
+        let chess960 = [
+            (queenside, 0, 4),
+            (kingside, 7, 4),
+            (black_queenside, 56, 60),
+            (black_kingside, 63, 60),
+        ]
+        .into_iter()
+        .any(|(rook, corner, king)| {
+            rook.is_some_and(|rook| rook != corner || pieces.king.0 & 1u64 << king == 0)
+        });
 
-        Ok(Board {
+        let mut board = Board {
             pieces,
             state,
             halfmoves,
             fullmoves,
             side_to_move,
-        })
+            chess960,
+            hash: 0,
+            pawn_hash: 0,
+            history: vec![],
+        };
+        // the king could be taken, no legal game gets here
+        let (checkers, _) = board.checkers_pinners(!side_to_move);
+        if checkers.count() > 0 {
+            return Err(FenError::OpponentInCheck);
+        }
+        board.hash = board.compute_hash();
+        board.pawn_hash = board.compute_pawn_hash();
+        Ok(board)
+    }
+}
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side_to_move = match self.side_to_move {
+            Color::White => 'w',
+            Color::Black => 'b',
+        };
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.pieces,
+            side_to_move,
+            self.state.castling_fen(&self.pieces, false),
+            self.state.en_pessant_fen(),
+            self.halfmoves,
+            self.fullmoves
+        )
     }
 }
 impl Default for Board {
@@ -48,6 +151,15 @@ impl Default for Board {
     }
 }
 impl Board {
+    pub fn to_fen(&self) -> String {
+        self.to_string()
+    }
+    // castling rights as rook files, "HAha" instead of "KQkq"
+    pub fn to_shredder_fen(&self) -> String {
+        let mut fen: Vec<String> = self.to_fen().split(' ').map(str::to_owned).collect();
+        fen[2] = self.state.castling_fen(&self.pieces, true);
+        fen.join(" ")
+    }
     pub const fn colors(&self, color: Color) -> BitBoard {
         match color {
             Color::White => self.pieces.white,
@@ -110,7 +222,9 @@ impl Board {
             }
             attackers &= attackers - 1;
         }
-        checkers |= KNIGHT_LOOKUP[our_king.trailing_zeros() as usize].0 & their_pieces;
+        checkers |= KNIGHT_LOOKUP[our_king.trailing_zeros() as usize].0
+            & self.pieces.knight.0
+            & their_pieces;
         checkers |= self
             .pawn_attacks(our_king.trailing_zeros() as usize, color)
             .0
@@ -126,7 +240,7 @@ impl Board {
     ) -> (BitBoard, PinMask) {
         let our_king = self.colors(color).0 & self.pieces(PieceType::King).0;
         let mut sliders = checkers.0 & !KNIGHT_LOOKUP[our_king.trailing_zeros() as usize].0;
-        let mut checkmask = 0;
+        let mut checkmask = checkers.0;
         // dbg!(sliders);
         while sliders > 0 {
             checkmask |= between_rays(
@@ -137,30 +251,26 @@ impl Board {
             // dbg!(checkmask);
             sliders &= sliders - 1;
         }
-        checkmask &= !our_king | KNIGHT_LOOKUP[our_king.trailing_zeros() as usize].0;
+        if checkers.0 == 0 {
+            checkmask = !0;
+        }
 
-        let mut pinmask = PinMask {
-            h: BitBoard::EMPTY,
-            v: BitBoard::EMPTY,
-            d1: BitBoard::EMPTY,
-            d2: BitBoard::EMPTY,
-        };
-        let king_rank = (our_king.trailing_zeros() >> 3) as usize;
-        let king_file = (our_king.trailing_zeros() & 7) as usize;
+        let mut pinmask = PinMask::EMPTY;
+        let king_rank = (our_king.trailing_zeros() >> 3) as i8;
+        let king_file = (our_king.trailing_zeros() & 7) as i8;
         while pinners.0 > 0 {
-            let rank = pinners.first() >> 3;
-            let file = pinners.first() & 7;
-            let pin = (between_rays(BitBoard(our_king), BitBoard(1 << pinners.first())).0
-                | 1u64 << pinners.first())
-                & !self.colors(color).0;
+            let rank = (pinners.first() >> 3) as i8;
+            let file = (pinners.first() & 7) as i8;
+            let pin = between_rays(BitBoard(our_king), BitBoard(1 << pinners.first())).0
+                | 1u64 << pinners.first();
             if king_rank == rank {
-                pinmask.h = BitBoard(pin)
+                pinmask.h.0 |= pin
             } else if king_file == file {
-                pinmask.v = BitBoard(pin)
+                pinmask.v.0 |= pin
             } else if king_rank - rank == king_file - file {
-                pinmask.d1 = BitBoard(pin)
+                pinmask.d1.0 |= pin
             } else if king_rank - rank == file - king_file {
-                pinmask.d2 = BitBoard(pin)
+                pinmask.d2.0 |= pin
             } else {
                 panic!("invalid pin")
             }
@@ -168,11 +278,627 @@ impl Board {
         }
         (BitBoard(checkmask), pinmask)
     }
-    pub const fn king_moves(&self, color: Color) -> Vec<Move> {
-        let mut moves: Vec<Move> = vec![];
-        let king_idx = (self.colors(color).0 & self.pieces(PieceType::King).0).trailing_zeros();
-        // let mut moves_bb;
+    // every square attacked by `color`, sliders see through nothing but `occupied`
+    pub fn attacks(&self, color: Color, occupied: BitBoard) -> BitBoard {
+        let ours = self.colors(color).0;
+        let mut attacks = 0u64;
+        let mut rooks = (self.pieces.rook.0 | self.pieces.queen.0) & ours;
+        while rooks > 0 {
+            attacks |= rook_attacks(rooks.trailing_zeros() as usize, occupied).0;
+            rooks &= rooks - 1;
+        }
+        let mut bishops = (self.pieces.bishop.0 | self.pieces.queen.0) & ours;
+        while bishops > 0 {
+            attacks |= bishop_attacks(bishops.trailing_zeros() as usize, occupied).0;
+            bishops &= bishops - 1;
+        }
+        let mut knights = self.pieces.knight.0 & ours;
+        while knights > 0 {
+            attacks |= KNIGHT_LOOKUP[knights.trailing_zeros() as usize].0;
+            knights &= knights - 1;
+        }
+        let mut pawns = self.pieces.pawn.0 & ours;
+        while pawns > 0 {
+            attacks |= self.pawn_attacks(pawns.trailing_zeros() as usize, color).0;
+            pawns &= pawns - 1;
+        }
+        let king = self.pieces.king.0 & ours;
+        if king > 0 {
+            attacks |= KING_LOOKUP[king.trailing_zeros() as usize].0;
+        }
+        BitBoard(attacks)
+    }
+    pub const fn piece_on(&self, index: usize) -> Option<PieceType> {
+        let square = 1u64 << index;
+        if self.pieces.pawn.0 & square > 0 {
+            Some(PieceType::Pawn)
+        } else if self.pieces.knight.0 & square > 0 {
+            Some(PieceType::Knight)
+        } else if self.pieces.bishop.0 & square > 0 {
+            Some(PieceType::Bishop)
+        } else if self.pieces.rook.0 & square > 0 {
+            Some(PieceType::Rook)
+        } else if self.pieces.queen.0 & square > 0 {
+            Some(PieceType::Queen)
+        } else if self.pieces.king.0 & square > 0 {
+            Some(PieceType::King)
+        } else {
+            None
+        }
+    }
+    fn toggle(&mut self, color: Color, piece_type: PieceType, index: usize) {
+        let square = BitBoard::new(index);
+        match piece_type {
+            PieceType::King => self.pieces.king ^= square,
+            PieceType::Queen => self.pieces.queen ^= square,
+            PieceType::Rook => self.pieces.rook ^= square,
+            PieceType::Bishop => self.pieces.bishop ^= square,
+            PieceType::Knight => self.pieces.knight ^= square,
+            PieceType::Pawn => self.pieces.pawn ^= square,
+        }
+        match color {
+            Color::White => self.pieces.white ^= square,
+            Color::Black => self.pieces.black ^= square,
+        }
+        self.hash ^= ZOBRIST.piece(color, piece_type, index);
+        if piece_type == PieceType::Pawn {
+            self.pawn_hash ^= ZOBRIST.piece(color, piece_type, index);
+        }
+    }
+    // (rook_from, king_to, rook_to) if `piece_move` is a castling move for `color` under `state`,
+    // chess960 castles king-takes-rook while standard chess names the king's destination
+    fn castling(
+        &self,
+        color: Color,
+        piece_move: Move,
+        state: &GameState,
+    ) -> Option<(usize, usize, usize)> {
+        let (from, to) = (piece_move.from(), piece_move.to());
+        let (queenside, kingside) = state.rooks(color);
+        let rank = from & 56;
+        [queenside, kingside]
+            .into_iter()
+            .flatten()
+            .find_map(|rook| {
+                let (king_to, rook_to) = match rook > from {
+                    true => (rank + 6, rank + 5),
+                    false => (rank + 2, rank + 3),
+                };
+                let standard = !self.chess960 && to == king_to && from.abs_diff(to) == 2;
+                (to == rook || standard).then_some((rook, king_to, rook_to))
+            })
+    }
+    //function does not check for legality, that is the job of the movegen
+    pub fn make_move(&mut self, piece_move: Move) -> Undo {
+        let color = self.side_to_move;
+        let (from, to) = (piece_move.from(), piece_move.to());
+        let piece = self.piece_on(from).expect("no piece to move");
+        let castling = match piece {
+            PieceType::King => self.castling(color, piece_move, &self.state),
+            _ => None,
+        };
+        let undo = Undo {
+            captured: castling.map_or(self.piece_on(to), |_| None),
+            state: self.state,
+            halfmoves: self.halfmoves,
+            hash: self.hash,
+        };
+        self.history.push(self.hash);
+        self.hash ^= self.state_hash();
+
+        if let Some(captured) = undo.captured {
+            self.toggle(!color, captured, to);
+        }
+        // both pieces leave before either lands, the squares may overlap in chess960
+        if let Some((rook_from, king_to, rook_to)) = castling {
+            self.toggle(color, PieceType::King, from);
+            self.toggle(color, PieceType::Rook, rook_from);
+            self.toggle(color, PieceType::King, king_to);
+            self.toggle(color, PieceType::Rook, rook_to);
+        } else {
+            self.toggle(color, piece, from);
+            self.toggle(color, piece_move.promotion().unwrap_or(piece), to);
+        }
+
+        self.halfmoves += 1;
+        self.state.en_pessant = None;
+        match piece {
+            PieceType::Pawn => {
+                self.halfmoves = 0;
+                if Some(to) == undo.state.en_pessant {
+                    let captured = if to > from { to - 8 } else { to + 8 };
+                    self.toggle(!color, PieceType::Pawn, captured);
+                } else if from.abs_diff(to) == 16 {
+                    self.state.en_pessant = Some((from + to) / 2);
+                }
+            }
+            PieceType::King => match color {
+                Color::White => (self.state.castling.0, self.state.castling.1) = (None, None),
+                Color::Black => (self.state.castling.2, self.state.castling.3) = (None, None),
+            },
+            _ => {}
+        }
+        if undo.captured.is_some() {
+            self.halfmoves = 0;
+        }
+        // a castling rook leaving or being taken loses that side's right
+        for square in [from, to] {
+            let castling = &mut self.state.castling;
+            for right in [
+                &mut castling.0,
+                &mut castling.1,
+                &mut castling.2,
+                &mut castling.3,
+            ] {
+                if *right == Some(square) {
+                    *right = None;
+                }
+            }
+        }
+
+        if color == Color::Black {
+            self.fullmoves += 1;
+        }
+        self.side_to_move = !color;
+        self.hash ^= self.state_hash() ^ ZOBRIST.side;
+        undo
+    }
+    pub fn unmake_move(&mut self, piece_move: Move, undo: Undo) {
+        let color = !self.side_to_move;
+        let (from, to) = (piece_move.from(), piece_move.to());
+        let castling = self
+            .castling(color, piece_move, &undo.state)
+            .filter(|&(_, king_to, _)| {
+                self.colors(color).0 & self.pieces.king.0 == 1u64 << king_to
+            });
+        if let Some((rook_from, king_to, rook_to)) = castling {
+            self.toggle(color, PieceType::King, king_to);
+            self.toggle(color, PieceType::Rook, rook_to);
+            self.toggle(color, PieceType::King, from);
+            self.toggle(color, PieceType::Rook, rook_from);
+            if color == Color::Black {
+                self.fullmoves -= 1;
+            }
+            self.side_to_move = color;
+            self.state = undo.state;
+            self.halfmoves = undo.halfmoves;
+            self.hash = undo.hash;
+            self.history.pop();
+            return;
+        }
+        let moved = self.piece_on(to).expect("no piece to unmake");
+        let piece = match piece_move.promotion() {
+            Some(_) => PieceType::Pawn,
+            None => moved,
+        };
+
+        self.toggle(color, moved, to);
+        self.toggle(color, piece, from);
+        if let Some(captured) = undo.captured {
+            self.toggle(!color, captured, to);
+        }
+        if piece == PieceType::Pawn && Some(to) == undo.state.en_pessant {
+            let captured = if to > from { to - 8 } else { to + 8 };
+            self.toggle(!color, PieceType::Pawn, captured);
+        }
+
+        if color == Color::Black {
+            self.fullmoves -= 1;
+        }
+        self.side_to_move = color;
+        self.state = undo.state;
+        self.halfmoves = undo.halfmoves;
+        self.hash = undo.hash;
+        self.history.pop();
+    }
+    // the legal move between these squares, with its kind filled in
+    pub fn find_move(&self, from: usize, to: usize, promotion: Option<PieceType>) -> Option<Move> {
+        self.legal_moves().into_iter().find(|piece_move| {
+            piece_move.from() == from
+                && piece_move.to() == to
+                && piece_move.promotion() == promotion
+        })
+    }
+    pub fn legal_moves(&self) -> MoveList {
+        self.generate(GenMode::All)
+    }
+    /// The legal moves of one kind, so the search can take them a stage at a time.
+    pub fn generate(&self, mode: GenMode) -> MoveList {
+        let mut moves = MoveList::new();
+        let color = self.side_to_move;
+        let (checkers, pinners) = self.checkers_pinners(color);
+        if mode == GenMode::Evasions && checkers.count() == 0 {
+            return moves;
+        }
+        let mut target = 0;
+        if mode.captures() {
+            target |= self.colors(!color).0;
+        }
+        if mode.quiets() {
+            target |= !self.occupied().0;
+        }
+        let target = BitBoard(target);
+        self.king_moves(color, target, mode.quiets(), &mut moves);
+        if checkers.count() < 2 {
+            let (checkmask, pinmask) = self.checkmask_pinmask(color, checkers, pinners);
+            let target = BitBoard(target.0 & checkmask.0);
+            self.queen_moves(color, target, pinmask, &mut moves);
+            self.rook_moves(color, target, pinmask, &mut moves);
+            self.bishop_moves(color, target, pinmask, &mut moves);
+            self.knight_moves(color, target, pinmask, &mut moves);
+            self.pawn_moves(color, checkmask, pinmask, mode, &mut moves);
+        }
+        if mode == GenMode::QuietChecks {
+            moves.retain(|&piece_move| self.gives_check(piece_move));
+        }
+        moves
+    }
+    /// Whether `piece_move` checks the other king, worked out without playing it.
+    pub fn gives_check(&self, piece_move: Move) -> bool {
+        let color = self.side_to_move;
+        let (from, to) = (piece_move.from(), piece_move.to());
+        let Some(piece) = self.piece_on(from) else {
+            return false;
+        };
+        let their_king = (self.colors(!color).0 & self.pieces.king.0).trailing_zeros() as usize;
+        let mut occupied = self.occupied().0 & !(1u64 << from);
+        // the castling rook is the only piece that can give check
+        let (piece, to) = match self.castling(color, piece_move, &self.state) {
+            Some((rook_from, king_to, rook_to)) if piece_move.is_castling() => {
+                occupied = occupied & !(1u64 << rook_from) | 1u64 << king_to;
+                (PieceType::Rook, rook_to)
+            }
+            _ => (piece_move.promotion().unwrap_or(piece), to),
+        };
+        occupied |= 1u64 << to;
+        if piece_move.is_en_passant() {
+            occupied &= !(1u64 << if to > from { to - 8 } else { to + 8 });
+        }
+        let direct = match piece {
+            PieceType::King => 0,
+            PieceType::Queen => queen_attacks(to, BitBoard(occupied)).0,
+            PieceType::Rook => rook_attacks(to, BitBoard(occupied)).0,
+            PieceType::Bishop => bishop_attacks(to, BitBoard(occupied)).0,
+            PieceType::Knight => KNIGHT_LOOKUP[to].0,
+            PieceType::Pawn => self.pawn_attacks(to, color).0,
+        };
+        if direct & 1u64 << their_king > 0 {
+            return true;
+        }
+        // sliders behind the piece that left
+        let ours = self.colors(color).0 & occupied & !(1u64 << to);
+        let straight = (self.pieces.rook.0 | self.pieces.queen.0) & ours;
+        let diagonal = (self.pieces.bishop.0 | self.pieces.queen.0) & ours;
+        rook_attacks(their_king, BitBoard(occupied)).0 & straight
+            | bishop_attacks(their_king, BitBoard(occupied)).0 & diagonal
+            > 0
+    }
+    pub fn king_moves(&self, color: Color, target: BitBoard, castling: bool, moves: &mut MoveList) {
+        let our_king = self.colors(color).0 & self.pieces(PieceType::King).0;
+        let king_idx = our_king.trailing_zeros() as usize;
+        // the king must not hide behind itself from a slider
+        let danger = self
+            .attacks(!color, BitBoard(self.occupied().0 & !our_king))
+            .0;
+        let mut moves_bb = KING_LOOKUP[king_idx].0 & target.0 & !danger;
+        while moves_bb > 0 {
+            moves.push(Move::new(
+                king_idx,
+                moves_bb.trailing_zeros() as usize,
+                None,
+            ));
+            moves_bb &= moves_bb - 1;
+        }
+        if !castling || danger & our_king > 0 {
+            return;
+        }
+        let (queenside, kingside) = self.state.rooks(color);
+        let our_rooks = self.colors(color).0 & self.pieces.rook.0;
+        let rank = king_idx & 56;
+        for rook in [kingside, queenside].into_iter().flatten() {
+            let (king_to, rook_to) = match rook > king_idx {
+                true => (rank + 6, rank + 5),
+                false => (rank + 2, rank + 3),
+            };
+            let castlers = our_king | 1u64 << rook;
+            if our_rooks & 1u64 << rook == 0 || king_idx >> 3 != rook >> 3 {
+                continue;
+            }
+            let king_path =
+                between_rays(BitBoard(our_king), BitBoard::new(king_to)).0 | 1u64 << king_to;
+            let rook_path =
+                between_rays(BitBoard::new(rook), BitBoard::new(rook_to)).0 | 1u64 << rook_to;
+            if (king_path | rook_path) & self.occupied().0 & !castlers > 0 {
+                continue;
+            }
+            // the castling rook may be all that shields the king's new square
+            let danger = self
+                .attacks(!color, BitBoard(self.occupied().0 & !castlers))
+                .0;
+            if danger & (king_path | our_king) > 0 {
+                continue;
+            }
+            let to = if self.chess960 { rook } else { king_to };
+            moves.push(Move::castling(king_idx, to));
+        }
+    }
+    fn slider_moves(
+        &self,
+        mut pieces: u64,
+        slider_attacks: fn(usize, BitBoard) -> BitBoard,
+        target: BitBoard,
+        pinmask: PinMask,
+        moves: &mut MoveList,
+    ) {
+        while pieces > 0 {
+            let from = pieces.trailing_zeros() as usize;
+            let mut moves_bb =
+                slider_attacks(from, self.occupied()).0 & target.0 & pinmask.movemask(from).0;
+            while moves_bb > 0 {
+                moves.push(Move::new(from, moves_bb.trailing_zeros() as usize, None));
+                moves_bb &= moves_bb - 1;
+            }
+            pieces &= pieces - 1;
+        }
+    }
+    // `target` holds the squares moves may land on, checks and all
+    pub fn queen_moves(
+        &self,
+        color: Color,
+        target: BitBoard,
+        pinmask: PinMask,
+        moves: &mut MoveList,
+    ) {
+        let queens = self.colors(color).0 & self.pieces.queen.0;
+        self.slider_moves(queens, queen_attacks, target, pinmask, moves)
+    }
+    pub fn rook_moves(
+        &self,
+        color: Color,
+        target: BitBoard,
+        pinmask: PinMask,
+        moves: &mut MoveList,
+    ) {
+        let rooks = self.colors(color).0 & self.pieces.rook.0;
+        self.slider_moves(rooks, rook_attacks, target, pinmask, moves)
+    }
+    pub fn bishop_moves(
+        &self,
+        color: Color,
+        target: BitBoard,
+        pinmask: PinMask,
+        moves: &mut MoveList,
+    ) {
+        let bishops = self.colors(color).0 & self.pieces.bishop.0;
+        self.slider_moves(bishops, bishop_attacks, target, pinmask, moves)
+    }
+    pub fn knight_moves(
+        &self,
+        color: Color,
+        target: BitBoard,
+        pinmask: PinMask,
+        moves: &mut MoveList,
+    ) {
+        // a pinned knight can never stay on its pin ray
+        let mut knights = self.colors(color).0
+            & self.pieces.knight.0
+            & !(pinmask.h | pinmask.v | pinmask.d1 | pinmask.d2).0;
+        while knights > 0 {
+            let from = knights.trailing_zeros() as usize;
+            let mut moves_bb = KNIGHT_LOOKUP[from].0 & target.0;
+            while moves_bb > 0 {
+                moves.push(Move::new(from, moves_bb.trailing_zeros() as usize, None));
+                moves_bb &= moves_bb - 1;
+            }
+            knights &= knights - 1;
+        }
+    }
+    // promotions count as captures, they change the material as much
+    pub fn pawn_moves(
+        &self,
+        color: Color,
+        checkmask: BitBoard,
+        pinmask: PinMask,
+        mode: GenMode,
+        moves: &mut MoveList,
+    ) {
+        let (captures, quiets) = (mode.captures(), mode.quiets());
+        let empty = !self.occupied().0;
+        let their_pieces = self.colors(!color).0;
+        let promotion_ranks = 0xff000000000000ffu64;
+        let mut pawns = self.colors(color).0 & self.pieces.pawn.0;
+        while pawns > 0 {
+            let from = pawns.trailing_zeros() as usize;
+            let pawn = 1u64 << from;
+            let (single, double) = match color {
+                Color::White => (pawn << 8 & empty, (pawn & 0xff00) << 16 & empty),
+                Color::Black => (pawn >> 8 & empty, (pawn & 0xff000000000000) >> 16 & empty),
+            };
+            let mut pushes = single;
+            if single > 0 {
+                pushes |= double;
+            }
+            let mut moves_bb = 0;
+            if captures {
+                moves_bb |= self.pawn_attacks(from, color).0 & their_pieces;
+                moves_bb |= pushes & promotion_ranks;
+            }
+            if quiets {
+                moves_bb |= pushes & !promotion_ranks;
+            }
+            moves_bb &= checkmask.0 & pinmask.movemask(from).0;
+            while moves_bb > 0 {
+                let to = moves_bb.trailing_zeros() as usize;
+                if to >> 3 == 0 || to >> 3 == 7 {
+                    for promotion in [
+                        PieceType::Queen,
+                        PieceType::Rook,
+                        PieceType::Bishop,
+                        PieceType::Knight,
+                    ] {
+                        moves.push(Move::new(from, to, Some(promotion)));
+                    }
+                } else if from.abs_diff(to) == 16 {
+                    moves.push(Move::double_push(from, to));
+                } else {
+                    moves.push(Move::new(from, to, None));
+                }
+                moves_bb &= moves_bb - 1;
+            }
+            if let Some(en_pessant) = self.state.en_pessant.filter(|_| captures) {
+                if self.pawn_attacks(from, color).0 & 1u64 << en_pessant > 0
+                    && self.en_pessant_legal(color, from, en_pessant)
+                {
+                    moves.push(Move::en_passant(from, en_pessant));
+                }
+            }
+            pawns &= pawns - 1;
+        }
+    }
+    // en passant removes two pieces from the board at once, so pins and checks are
+    // resolved by replaying the capture instead of trusting the masks
+    fn en_pessant_legal(&self, color: Color, from: usize, to: usize) -> bool {
+        let captured = match color {
+            Color::White => to - 8,
+            Color::Black => to + 8,
+        };
+        let our_king = self.colors(color).0 & self.pieces.king.0;
+        let king_idx = our_king.trailing_zeros() as usize;
+        let their_pieces = self.colors(!color).0 & !(1u64 << captured);
+        let occupied =
+            BitBoard((self.occupied().0 & !(1u64 << from | 1u64 << captured)) | 1u64 << to);
+        let checkers = rook_attacks(king_idx, occupied).0
+            & (self.pieces.rook.0 | self.pieces.queen.0)
+            & their_pieces
+            | bishop_attacks(king_idx, occupied).0
+                & (self.pieces.bishop.0 | self.pieces.queen.0)
+                & their_pieces
+            | KNIGHT_LOOKUP[king_idx].0 & self.pieces.knight.0 & their_pieces
+            | self.pawn_attacks(king_idx, color).0 & self.pieces.pawn.0 & their_pieces;
+        checkers == 0
+    }
+}
 
-        todo!()
+#[test]
+fn startpos_moves() {
+    assert_eq!(Board::default().legal_moves().len(), 20);
+}
+#[test]
+fn double_check_only_king_moves() {
+    // knight on f6 and rook on e1 both give check, the bishop could only take the knight
+    let board = "4k3/6b1/5N2/8/8/8/8/4R1K1 b - - 0 1"
+        .parse::<Board>()
+        .ok()
+        .unwrap();
+    let moves = board.legal_moves();
+    assert!(moves.iter().all(|m| m.from() == 60));
+    assert_eq!(moves.len(), 3);
+}
+#[test]
+fn en_pessant_horizontal_pin() {
+    // capturing en passant would expose the king on the fifth rank
+    let board = "8/8/8/KPp4r/8/8/8/7k w - c6 0 2"
+        .parse::<Board>()
+        .ok()
+        .unwrap();
+    assert!(!board.legal_moves().contains(&Move::en_passant(33, 42)));
+}
+#[test]
+fn make_unmake_random_games() {
+    let mut seed = 0x9e3779b97f4a7c15u64;
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9",
+        "2r1kr2/8/8/8/8/8/8/1R2K1R1 w GBfc - 0 1",
+    ] {
+        let start = fen.parse::<Board>().ok().unwrap();
+        for _ in 0..20 {
+            let mut board = start.clone();
+            let mut history = vec![];
+            for _ in 0..80 {
+                let moves = board.legal_moves();
+                if moves.is_empty() {
+                    break;
+                }
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                let piece_move = moves[seed as usize % moves.len()];
+                let before = board.clone();
+                let undo = board.make_move(piece_move);
+                let after = board.clone();
+                board.unmake_move(piece_move, undo);
+                assert_eq!(board, before);
+                board = after;
+                history.push((piece_move, undo));
+            }
+            while let Some((piece_move, undo)) = history.pop() {
+                board.unmake_move(piece_move, undo);
+            }
+            assert_eq!(board, start);
+        }
+    }
+}
+#[cfg(test)]
+fn check_stages(board: &mut Board, depth: usize) {
+    let all = board.legal_moves();
+    let captures = board.generate(GenMode::Captures);
+    let quiets = board.generate(GenMode::Quiets);
+    // the stages split the moves between them without overlap
+    assert_eq!(captures.len() + quiets.len(), all.len(), "{}", board);
+    assert!(captures.iter().chain(&quiets).all(|m| all.contains(m)));
+    assert!(captures
+        .iter()
+        .all(|&m| board.captured(m).is_some() || m.promotion().is_some()));
+    let evasions = board.generate(GenMode::Evasions);
+    match board.is_check() {
+        true => assert_eq!(evasions, all),
+        false => assert!(evasions.is_empty()),
+    }
+    let quiet_checks = board.generate(GenMode::QuietChecks);
+    for &piece_move in &all {
+        let undo = board.make_move(piece_move);
+        let check = board.is_check();
+        if depth > 1 {
+            check_stages(board, depth - 1);
+        }
+        board.unmake_move(piece_move, undo);
+        assert_eq!(
+            board.gives_check(piece_move),
+            check,
+            "{} {}",
+            board,
+            piece_move
+        );
+        assert_eq!(
+            quiet_checks.contains(&piece_move),
+            check && quiets.contains(&piece_move),
+            "{} {}",
+            board,
+            piece_move
+        );
+    }
+}
+#[test]
+fn staged_generation() {
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+        "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9",
+    ] {
+        check_stages(&mut fen.parse::<Board>().unwrap(), 2);
     }
 }
+#[test]
+fn move_list_capacity() {
+    // the most legal moves any position is known to have
+    let board = "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1"
+        .parse::<Board>()
+        .unwrap();
+    assert_eq!(board.legal_moves().len(), 218);
+}
@@ -0,0 +1,475 @@
+use crate::bitboard::{BitBoard, LIGHT_SQUARES};
+use crate::pawns::{free_passers, pawn_attacks, pawn_structure, PawnEntry, PawnTable};
+use crate::sliding_pieces::{bishop_attacks, queen_attacks, rook_attacks};
+use crate::{board::Board, color::Color, pieces::Pieces, PieceType};
+use crate::{KING_LOOKUP, KNIGHT_LOOKUP};
+
+// middlegame and endgame values, the endgame favours rooks and pawns over minors
+const MATERIAL: [(i32, i32); 6] = [
+    (0, 0),
+    (1000, 940),
+    (480, 510),
+    (360, 300),
+    (340, 285),
+    (85, 95),
+];
+// what each piece adds to the middlegame phase, 24 with all pieces on the board
+const PHASE: [i32; 6] = [0, 4, 2, 1, 1, 0];
+const MAX_PHASE: i32 = 24;
+
+// per reachable square over the typical count, indexed like PieceType
+const MOBILITY: [(i32, i32); 6] = [(0, 0), (1, 2), (2, 4), (5, 5), (4, 4), (0, 0)];
+const MOBILITY_BASE: [i32; 6] = [0, 12, 7, 6, 4, 0];
+// attack units per square of the king zone a piece hits
+const KING_ATTACK_UNITS: [i32; 6] = [0, 5, 3, 2, 2, 0];
+// for a pawn one and two squares in front of the king, on the king's file and either side
+const PAWN_SHIELD: [i32; 2] = [15, 8];
+const MISSING_SHIELD: i32 = -20;
+// enemy pawns one to three squares in front of the king's files
+const PAWN_STORM: [i32; 3] = [-25, -15, -5];
+const BISHOP_PAIR: (i32, i32) = (30, 50);
+const UNDEFENDED: (i32, i32) = (-10, -10);
+
+// piece-square tables from white's side, a8 first so they read like a board
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     10,  10,   0, -10, -10,   0,  10,  10,
+     20,  30,  10,  -5,   0,  10,  30,  20,
+];
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+#[rustfmt::skip]
+const QUEEN_MG: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,   0,  -5, -10, -10, -20,
+];
+#[rustfmt::skip]
+const QUEEN_EG: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+    -10,   5,  10,  10,  10,  10,   5, -10,
+     -5,   5,  10,  15,  15,  10,   5,  -5,
+     -5,   5,  10,  15,  15,  10,   5,  -5,
+    -10,   5,  10,  10,  10,  10,   5, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+#[rustfmt::skip]
+const ROOK_MG: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+];
+#[rustfmt::skip]
+const ROOK_EG: [i32; 64] = [
+      5,   5,   5,   5,   5,   5,   5,   5,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+];
+#[rustfmt::skip]
+const BISHOP_MG: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+#[rustfmt::skip]
+const BISHOP_EG: [i32; 64] = [
+    -15, -10, -10, -10, -10, -10, -10, -15,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -15, -10, -10, -10, -10, -10, -10, -15,
+];
+#[rustfmt::skip]
+const KNIGHT_MG: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+#[rustfmt::skip]
+const KNIGHT_EG: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  10,  10,  10,   0, -30,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   0,  10,  10,  10,  10,   0, -30,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     80,  80,  80,  80,  80,  80,  80,  80,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     30,  30,  30,  30,  30,  30,  30,  30,
+     15,  15,  15,  15,  15,  15,  15,  15,
+      5,   5,   5,   5,   5,   5,   5,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+// indexed like PieceType
+const TABLES: [(&[i32; 64], &[i32; 64]); 6] = [
+    (&KING_MG, &KING_EG),
+    (&QUEEN_MG, &QUEEN_EG),
+    (&ROOK_MG, &ROOK_EG),
+    (&BISHOP_MG, &BISHOP_EG),
+    (&KNIGHT_MG, &KNIGHT_EG),
+    (&PAWN_MG, &PAWN_EG),
+];
+
+const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
+
+const fn bitboard(pieces: &Pieces, piece_type: PieceType) -> u64 {
+    match piece_type {
+        PieceType::King => pieces.king.0,
+        PieceType::Queen => pieces.queen.0,
+        PieceType::Rook => pieces.rook.0,
+        PieceType::Bishop => pieces.bishop.0,
+        PieceType::Knight => pieces.knight.0,
+        PieceType::Pawn => pieces.pawn.0,
+    }
+}
+
+// 24 in the opening down to 0 with only kings and pawns left
+pub fn phase(pieces: &Pieces) -> i32 {
+    let phase: i32 = PIECE_TYPES
+        .into_iter()
+        .map(|piece_type| {
+            PHASE[piece_type as usize] * bitboard(pieces, piece_type).count_ones() as i32
+        })
+        .sum();
+    phase.min(MAX_PHASE)
+}
+
+// (middlegame, endgame) material and placement for white minus black
+fn material_psqt(pieces: &Pieces) -> (i32, i32) {
+    let (mut mg, mut eg) = (0, 0);
+    for piece_type in PIECE_TYPES {
+        let (mg_table, eg_table) = TABLES[piece_type as usize];
+        let (mg_value, eg_value) = MATERIAL[piece_type as usize];
+        for (color, sign) in [(pieces.white.0, 1), (pieces.black.0, -1)] {
+            let mut bb = bitboard(pieces, piece_type) & color;
+            while bb > 0 {
+                let index = bb.trailing_zeros() as usize;
+                // the tables start at a8, black looks at them upside down
+                let square = match sign {
+                    1 => index ^ 56,
+                    _ => index,
+                };
+                mg += sign * (mg_value + mg_table[square]);
+                eg += sign * (eg_value + eg_table[square]);
+                bb &= bb - 1;
+            }
+        }
+    }
+    (mg, eg)
+}
+
+// every attack of each piece type of `color`, indexed like PieceType
+fn attack_maps(pieces: &Pieces, color: Color) -> [u64; 6] {
+    let ours = match color {
+        Color::White => pieces.white.0,
+        Color::Black => pieces.black.0,
+    };
+    let occupied = BitBoard(pieces.white.0 | pieces.black.0);
+    let mut maps = [0; 6];
+    for piece_type in PIECE_TYPES {
+        let mut bb = bitboard(pieces, piece_type) & ours;
+        if piece_type == PieceType::Pawn {
+            maps[piece_type as usize] = pawn_attacks(color, bb);
+            continue;
+        }
+        while bb > 0 {
+            let index = bb.trailing_zeros() as usize;
+            maps[piece_type as usize] |= piece_attacks(piece_type, index, occupied);
+            bb &= bb - 1;
+        }
+    }
+    maps
+}
+fn piece_attacks(piece_type: PieceType, index: usize, occupied: BitBoard) -> u64 {
+    match piece_type {
+        PieceType::King => KING_LOOKUP[index].0,
+        PieceType::Queen => queen_attacks(index, occupied).0,
+        PieceType::Rook => rook_attacks(index, occupied).0,
+        PieceType::Bishop => bishop_attacks(index, occupied).0,
+        PieceType::Knight => KNIGHT_LOOKUP[index].0,
+        PieceType::Pawn => 0,
+    }
+}
+
+// bishops on both square colors
+fn bishop_pair(pieces: &Pieces, ours: u64) -> bool {
+    let bishops = pieces.bishop.0 & ours;
+    bishops & LIGHT_SQUARES > 0 && bishops & !LIGHT_SQUARES > 0
+}
+// (middlegame, endgame) for `color`: mobility, king safety, bishop pair and undefended pieces
+fn activity(pieces: &Pieces, color: Color) -> (i32, i32) {
+    let (ours, theirs) = match color {
+        Color::White => (pieces.white.0, pieces.black.0),
+        Color::Black => (pieces.black.0, pieces.white.0),
+    };
+    let occupied = BitBoard(ours | theirs);
+    let our_maps = attack_maps(pieces, color);
+    let their_maps = attack_maps(pieces, !color);
+    let (mut mg, mut eg) = (0, 0);
+
+    // squares covered by enemy pawns don't count as room to move
+    let safe = !ours & !their_maps[PieceType::Pawn as usize];
+    for piece_type in [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ] {
+        let (mg_weight, eg_weight) = MOBILITY[piece_type as usize];
+        let mut bb = bitboard(pieces, piece_type) & ours;
+        while bb > 0 {
+            let index = bb.trailing_zeros() as usize;
+            let squares = (piece_attacks(piece_type, index, occupied) & safe).count_ones() as i32;
+            mg += mg_weight * (squares - MOBILITY_BASE[piece_type as usize]);
+            eg += eg_weight * (squares - MOBILITY_BASE[piece_type as usize]);
+            bb &= bb - 1;
+        }
+    }
+
+    // enemy pieces bearing on the squares around our king
+    let king = (pieces.king.0 & ours).trailing_zeros() as usize;
+    let zone = KING_LOOKUP[king].0 | 1u64 << king;
+    let mut units = 0;
+    let mut attackers = 0;
+    for piece_type in [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ] {
+        let mut bb = bitboard(pieces, piece_type) & theirs;
+        while bb > 0 {
+            let hits = piece_attacks(piece_type, bb.trailing_zeros() as usize, occupied) & zone;
+            if hits > 0 {
+                attackers += 1;
+                units += KING_ATTACK_UNITS[piece_type as usize] * hits.count_ones() as i32;
+            }
+            bb &= bb - 1;
+        }
+    }
+    // a lone attacker is rarely dangerous, several grow quickly worse
+    if attackers >= 2 {
+        mg -= (units * units / 4).min(500);
+    }
+
+    // pawns in front of a king on its first two ranks, and enemy pawns rolling towards it
+    let king_rank = match color {
+        Color::White => king >> 3,
+        Color::Black => 7 - (king >> 3),
+    };
+    if king_rank <= 1 {
+        let (rank, file) = (king >> 3, king & 7);
+        for shield_file in file.saturating_sub(1)..=(file + 1).min(7) {
+            let mut sheltered = false;
+            for distance in 1..=3 {
+                let square = match color {
+                    Color::White => 1u64 << ((rank + distance) * 8 + shield_file),
+                    Color::Black => 1u64 << ((rank - distance) * 8 + shield_file),
+                };
+                if distance <= 2 && !sheltered && pieces.pawn.0 & ours & square > 0 {
+                    mg += PAWN_SHIELD[distance - 1];
+                    sheltered = true;
+                }
+                if pieces.pawn.0 & theirs & square > 0 {
+                    mg += PAWN_STORM[distance - 1];
+                }
+            }
+            if !sheltered {
+                mg += MISSING_SHIELD;
+            }
+        }
+    }
+
+    if bishop_pair(pieces, ours) {
+        mg += BISHOP_PAIR.0;
+        eg += BISHOP_PAIR.1;
+    }
+    // pieces nothing of ours protects
+    let defended = our_maps.iter().fold(0, |all, map| all | map);
+    let loose = ours & !pieces.pawn.0 & !pieces.king.0 & !defended;
+    mg += UNDEFENDED.0 * loose.count_ones() as i32;
+    eg += UNDEFENDED.1 * loose.count_ones() as i32;
+    (mg, eg)
+}
+
+fn blend(pieces: &Pieces, pawns: &PawnEntry) -> i32 {
+    let (mut mg, mut eg) = material_psqt(pieces);
+    let (white_mg, white_eg) = activity(pieces, Color::White);
+    let (black_mg, black_eg) = activity(pieces, Color::Black);
+    mg += white_mg - black_mg;
+    eg += white_eg - black_eg;
+    mg += pawns.mg;
+    eg += pawns.eg + free_passers(pieces, pawns.passed);
+    let phase = phase(pieces);
+    (mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE
+}
+
+/// Centipawns for white, blending the middlegame and endgame scores by game phase.
+pub fn evaluate_pieces(pieces: &Pieces) -> i32 {
+    blend(pieces, &pawn_structure(pieces))
+}
+
+// centipawns from the side to move's point of view, pawn structure comes from the cache
+pub fn evaluate(board: &Board, pawns: &mut PawnTable) -> i32 {
+    let score = blend(&board.pieces, &pawns.probe(&board.pieces, board.pawn_hash));
+    match board.side_to_move {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+#[cfg(test)]
+pub fn mirror(pieces: &Pieces) -> Pieces {
+    use crate::bitboard::BitBoard;
+    let flip = |bb: BitBoard| BitBoard(bb.0.swap_bytes());
+    Pieces {
+        king: flip(pieces.king),
+        queen: flip(pieces.queen),
+        rook: flip(pieces.rook),
+        bishop: flip(pieces.bishop),
+        knight: flip(pieces.knight),
+        pawn: flip(pieces.pawn),
+        white: flip(pieces.black),
+        black: flip(pieces.white),
+    }
+}
+#[cfg(test)]
+pub const EVAL_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "8/8/4k3/3p4/3P4/4K3/8/8 w - - 0 1",
+    "6k1/5p2/6p1/8/7p/8/6PP/6K1 b - - 0 1",
+];
+
+#[test]
+fn eval_color_symmetry() {
+    for fen in EVAL_POSITIONS {
+        let pieces = fen.parse::<Pieces>().unwrap();
+        assert_eq!(
+            evaluate_pieces(&pieces),
+            -evaluate_pieces(&mirror(&pieces)),
+            "{}",
+            fen
+        );
+    }
+    let start = Board::default();
+    assert_eq!(evaluate_pieces(&start.pieces), 0);
+}
+#[test]
+fn eval_phase_blend() {
+    let start = Board::default();
+    assert_eq!(phase(&start.pieces), MAX_PHASE);
+    let pawns = "8/8/4k3/3p4/3P4/4K3/8/8 w - - 0 1"
+        .parse::<Pieces>()
+        .unwrap();
+    assert_eq!(phase(&pawns), 0);
+    // an extra queen is worth about a queen
+    let queen = "4k3/8/8/8/8/8/8/3QK3 w - - 0 1".parse::<Board>().unwrap();
+    let pawns = &mut PawnTable::default();
+    assert!((800..1100).contains(&evaluate(&queen, pawns)));
+    let mut black = queen.clone();
+    black.side_to_move = Color::Black;
+    assert_eq!(evaluate(&black, pawns), -evaluate(&queen, pawns));
+    // the king heads for the centre once the pieces are gone
+    let centre = "4k3/8/8/8/3K4/8/8/8 w - - 0 1".parse::<Pieces>().unwrap();
+    let corner = "4k3/8/8/8/8/8/8/K7 w - - 0 1".parse::<Pieces>().unwrap();
+    assert!(evaluate_pieces(&centre) > evaluate_pieces(&corner));
+}
+#[test]
+fn eval_activity_terms() {
+    let white = |fen: &str| activity(&fen.parse::<Pieces>().unwrap(), Color::White);
+    let pair = |fen: &str| {
+        let pieces = fen.parse::<Pieces>().unwrap();
+        bishop_pair(&pieces, pieces.white.0)
+    };
+    assert!(pair("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"));
+    assert!(!pair("4k3/8/8/8/8/4B3/8/2B1K3 w - - 0 1"));
+    assert!(!pair("2b1kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+    // a centralised knight beats one in the corner
+    let (centre, _) = white("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+    let (corner, _) = white("4k3/8/8/8/8/8/8/N3K3 w - - 0 1");
+    assert!(centre > corner, "{} {}", centre, corner);
+    // pulling the pawns from in front of the castled king costs safety
+    let (sheltered, _) = white("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1");
+    let (open, _) = white("4k3/8/8/8/5PPP/8/8/6K1 w - - 0 1");
+    assert!(sheltered > open);
+    // queen and rook swarming the king
+    let (quiet, _) = white("6k1/8/8/8/8/8/5PPP/q5K1 w - - 0 1");
+    let (attacked, _) = white("6k1/8/8/8/8/5q2/5PPP/r5K1 w - - 0 1");
+    assert!(quiet > attacked, "{} {}", quiet, attacked);
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    MissingField(&'static str),
+    TooManyFields,
+    BadRankCount(usize),
+    // ranks are numbered 1 to 8 like on the board
+    BadRankLength(usize),
+    UnknownPiece(char),
+    BadKings,
+    // the side that just moved left its king in check
+    OpponentInCheck,
+    BadSide(String),
+    BadCastling(String),
+    BadEnPassant(String),
+    BadCounter(String),
+}
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {} field", field),
+            FenError::TooManyFields => write!(f, "too many fields"),
+            FenError::BadRankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            FenError::BadRankLength(rank) => write!(f, "rank {} does not have 8 squares", rank),
+            FenError::UnknownPiece(piece) => write!(f, "unknown piece '{}'", piece),
+            FenError::BadKings => write!(f, "each side needs exactly one king"),
+            FenError::OpponentInCheck => write!(f, "the side not to move is in check"),
+            FenError::BadSide(side) => write!(f, "invalid side to move '{}'", side),
+            FenError::BadCastling(castling) => write!(f, "invalid castling rights '{}'", castling),
+            FenError::BadEnPassant(square) => write!(f, "invalid en passant square '{}'", square),
+            FenError::BadCounter(counter) => write!(f, "invalid move counter '{}'", counter),
+        }
+    }
+}
+impl std::error::Error for FenError {}
+
+// "e4" -> 28
+pub fn parse_square(s: &str) -> Option<usize> {
+    match s.as_bytes() {
+        &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+            Some((file - b'a') as usize + 8 * (rank - b'1') as usize)
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn fen_errors() {
+    use crate::board::Board;
+    for (fen, error) in [
+        ("", FenError::MissingField("piece placement")),
+        (
+            "4k3/8/8/8/8/8/8/4K3",
+            FenError::MissingField("side to move"),
+        ),
+        ("4k3/8/8/8/8/8/8/4K3 w", FenError::MissingField("castling")),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w -",
+            FenError::MissingField("en passant"),
+        ),
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1 x", FenError::TooManyFields),
+        ("4k3/8/8/8/8/8/4K3 w - - 0 1", FenError::BadRankCount(7)),
+        ("4k3/8/8/9/8/8/8/4K3 w - - 0 1", FenError::UnknownPiece('9')),
+        ("4k3/8/8/7/8/8/8/4K3 w - - 0 1", FenError::BadRankLength(5)),
+        (
+            "4k3/8/8/4pp3/8/8/8/4K3 w - - 0 1",
+            FenError::BadRankLength(5),
+        ),
+        ("4k3/8/8/8/8/8/8/4X3 w - - 0 1", FenError::UnknownPiece('X')),
+        ("4k3/8/8/8/8/8/8/8 w - - 0 1", FenError::BadKings),
+        ("4k3/8/8/8/8/8/8/3KK3 w - - 0 1", FenError::BadKings),
+        (
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            FenError::BadSide("x".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w KK - 0 1",
+            FenError::BadCastling("KK".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w Kx - 0 1",
+            FenError::BadCastling("Kx".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - e9 0 1",
+            FenError::BadEnPassant("e9".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - e3 0 1",
+            FenError::BadEnPassant("e3".to_owned()),
+        ),
+        (
+            "4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1",
+            FenError::BadEnPassant("e6".to_owned()),
+        ),
+        (
+            "4k3/4p3/8/3Pp3/8/8/8/4K3 w - e6 0 1",
+            FenError::BadEnPassant("e6".to_owned()),
+        ),
+        (
+            "4k3/8/4n3/3Pp3/8/8/8/4K3 w - e6 0 1",
+            FenError::BadEnPassant("e6".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/3pP3/8/8/4K3 b - d3 0 1",
+            FenError::BadEnPassant("d3".to_owned()),
+        ),
+        ("4k3/8/8/8/8/8/4R3/4K3 w - - 0 1", FenError::OpponentInCheck),
+        ("4k3/8/8/8/8/8/4r3/4K3 b - - 0 1", FenError::OpponentInCheck),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+            FenError::BadCounter("x".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 -1",
+            FenError::BadCounter("-1".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+            FenError::BadCounter("0".to_owned()),
+        ),
+    ] {
+        assert_eq!(fen.parse::<Board>(), Err(error), "{}", fen);
+    }
+}
+#[test]
+fn fen_counters() {
+    use crate::board::Board;
+    let board = "4k3/8/8/8/8/8/8/4K3 b - - 42 117".parse::<Board>().unwrap();
+    assert_eq!((board.halfmoves, board.fullmoves), (42, 117));
+    let board = "4k3/8/8/8/8/8/8/4K3 b - -".parse::<Board>().unwrap();
+    assert_eq!((board.halfmoves, board.fullmoves), (0, 1));
+}
+#[test]
+fn fen_round_trip() {
+    use crate::board::Board;
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        "4k3/8/8/8/8/8/8/4K2R w K - 99 150",
+        "r3k3/8/8/8/8/8/8/4K3 b q - 12 61",
+        "8/8/8/8/8/8/8/K6k w - - 0 1",
+        "7k/PPPPPPPP/8/8/K7/8/pppppppp/8 b - - 0 77",
+    ] {
+        let board = fen.parse::<Board>().unwrap();
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(board.to_string().parse::<Board>(), Ok(board.clone()));
+    }
+}
+#[test]
+fn fen_after_moves() {
+    use crate::{board::Board, moves::Move};
+    let mut board = Board::default();
+    board.make_move(Move::new(12, 28, None));
+    assert_eq!(
+        board.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+    board.make_move(Move::new(57, 42, None));
+    board.make_move(Move::new(4, 12, None));
+    assert_eq!(
+        board.to_fen(),
+        "r1bqkbnr/pppppppp/2n5/8/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 2 2"
+    );
+}
+#[test]
+fn chess960_castling_fen() {
+    use crate::board::Board;
+    // X-FEN letters only where KQkq would be ambiguous
+    for (fen, x_fen, shredder) in [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1",
+        ),
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        ),
+        (
+            "4k3/8/8/8/8/8/8/R1R1K1RR w GC - 0 1",
+            "4k3/8/8/8/8/8/8/R1R1K1RR w GC - 0 1",
+            "4k3/8/8/8/8/8/8/R1R1K1RR w GC - 0 1",
+        ),
+        (
+            "4k3/8/8/8/8/8/8/R1R1K1RR w KQ - 0 1",
+            "4k3/8/8/8/8/8/8/R1R1K1RR w KQ - 0 1",
+            "4k3/8/8/8/8/8/8/R1R1K1RR w HA - 0 1",
+        ),
+    ] {
+        let board = fen.parse::<Board>().unwrap();
+        assert_eq!(board.to_fen(), x_fen);
+        assert_eq!(board.to_shredder_fen(), shredder);
+        assert_eq!(x_fen.parse::<Board>().unwrap().state, board.state);
+    }
+    assert!(
+        !"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+            .parse::<Board>()
+            .unwrap()
+            .chess960
+    );
+    assert!(
+        "4k3/8/8/8/8/8/8/R1R1K1RR w GC - 0 1"
+            .parse::<Board>()
+            .unwrap()
+            .chess960
+    );
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+        "4k3/8/8/8/8/8/8/R3K2R w KH - 0 1",
+        "4k3/8/8/8/8/8/8/R3K2R w E - 0 1",
+        "4k3/8/8/8/8/8/4K3/R6R w K - 0 1",
+    ] {
+        assert!(
+            matches!(fen.parse::<Board>(), Err(FenError::BadCastling(_))),
+            "{}",
+            fen
+        );
+    }
+}
@@ -1,31 +1,70 @@
 use std::str::FromStr;
 
+use crate::color::Color;
+use crate::fen::{parse_square, FenError};
+use crate::pieces::Pieces;
 // use crate::square::*;
 
+// castling rights are kept as the square of the rook that may castle,
+// ordered white queenside, white kingside, black queenside, black kingside
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct GameState {
-    castling: (bool, bool, bool, bool),
-    en_pessant: Option<usize>,
+    pub castling: (Option<usize>, Option<usize>, Option<usize>, Option<usize>),
+    pub en_pessant: Option<usize>,
 }
-pub struct ParseFenError;
 impl FromStr for GameState {
-    type Err = ParseFenError;
+    type Err = FenError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parsed: Vec<&str> = s.split(" ").collect();
-        let castle_rights = parsed[2].to_owned();
-        let mut castling = (false, false, false, false);
-        castling.0 = castle_rights.contains("Q");
-        castling.1 = castle_rights.contains("K");
-        castling.2 = castle_rights.contains("q");
-        castling.3 = castle_rights.contains("k");
-        let en_pessant = match parsed[3] {
-            "-" => None,
-            str => {
-                let mut chars = str.chars();
-                let file = chars.next().unwrap() as usize - 97; // 'a'
-                let rank = chars.next().unwrap() as usize - 49; // '1'
-                Some(file + 8 * rank)
+        let pieces = s.parse::<Pieces>()?;
+        let mut parsed = s.split_whitespace().skip(2);
+        let castle_rights = parsed.next().ok_or(FenError::MissingField("castling"))?;
+        let en_pessant = parsed.next().ok_or(FenError::MissingField("en passant"))?;
+
+        let bad_castling = || FenError::BadCastling(castle_rights.to_owned());
+        let mut castling = (None, None, None, None);
+        if castle_rights != "-" {
+            for right in castle_rights.chars() {
+                let (color, rank) = match right.is_ascii_uppercase() {
+                    true => (pieces.white, 0),
+                    false => (pieces.black, 56),
+                };
+                let king = (pieces.king & color).first();
+                let rooks = (pieces.rook & color).0 & 0xff << rank;
+                if king >> 3 != rank >> 3 {
+                    return Err(bad_castling());
+                }
+                // X-FEN K and Q name the outermost rook, Shredder-FEN names the file
+                let rook = match right.to_ascii_lowercase() {
+                    'k' => {
+                        let above = rooks & !(2u64 << king).wrapping_sub(1);
+                        Some(63u32.wrapping_sub(above.leading_zeros()))
+                    }
+                    'q' => Some((rooks & ((1u64 << king) - 1)).trailing_zeros()),
+                    file @ 'a'..='h' => Some(rank as u32 + (file as u8 - b'a') as u32),
+                    _ => None,
+                }
+                .map(|rook| rook as usize)
+                .filter(|&rook| rook < 64 && rooks & 1u64 << rook > 0 && rook != king)
+                .ok_or_else(bad_castling)?;
+                let flag = match (right.is_ascii_uppercase(), rook > king) {
+                    (true, false) => &mut castling.0,
+                    (true, true) => &mut castling.1,
+                    (false, false) => &mut castling.2,
+                    (false, true) => &mut castling.3,
+                };
+                if flag.is_some() {
+                    return Err(bad_castling());
+                }
+                *flag = Some(rook);
             }
+        }
+        let en_pessant = match en_pessant {
+            "-" => None,
+            str => match parse_square(str) {
+                // only ever behind a pawn that just made a double push
+                Some(square) if square >> 3 == 2 || square >> 3 == 5 => Some(square),
+                _ => return Err(FenError::BadEnPassant(str.to_owned())),
+            },
         };
         Ok(GameState {
             castling,
@@ -33,3 +72,54 @@ impl FromStr for GameState {
         })
     }
 }
+impl GameState {
+    // (queenside, kingside) castling rooks
+    pub const fn rooks(&self, color: Color) -> (Option<usize>, Option<usize>) {
+        match color {
+            Color::White => (self.castling.0, self.castling.1),
+            Color::Black => (self.castling.2, self.castling.3),
+        }
+    }
+    // X-FEN uses KQkq unless another rook stands further out on the same side,
+    // Shredder-FEN always writes the rook's file
+    pub fn castling_fen(&self, pieces: &Pieces, shredder: bool) -> String {
+        let mut castling = String::new();
+        for (rook, white, kingside) in [
+            (self.castling.1, true, true),
+            (self.castling.0, true, false),
+            (self.castling.3, false, true),
+            (self.castling.2, false, false),
+        ] {
+            let Some(rook) = rook else { continue };
+            let color = if white { pieces.white } else { pieces.black };
+            let rooks = (pieces.rook & color).0 & 0xff << (rook & 56);
+            let outermost = match kingside {
+                true => rooks >> rook == 1,
+                false => rooks & ((1u64 << rook) - 1) == 0,
+            };
+            let symbol = match (shredder || !outermost, kingside) {
+                (true, _) => (b'a' + (rook & 7) as u8) as char,
+                (false, true) => 'k',
+                (false, false) => 'q',
+            };
+            castling.push(match white {
+                true => symbol.to_ascii_uppercase(),
+                false => symbol,
+            });
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        castling
+    }
+    pub fn en_pessant_fen(&self) -> String {
+        match self.en_pessant {
+            Some(square) => format!(
+                "{}{}",
+                (b'a' + (square & 7) as u8) as char,
+                (b'1' + (square >> 3) as u8) as char
+            ),
+            None => "-".to_owned(),
+        }
+    }
+}
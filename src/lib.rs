@@ -0,0 +1,71 @@
+pub mod bitboard;
+pub mod board;
+pub mod color;
+pub mod eval;
+pub mod fen;
+pub mod gamestate;
+pub mod lookup;
+pub mod magic;
+pub mod moves;
+pub mod notation;
+pub mod outcome;
+pub mod pawns;
+pub mod perft;
+#[cfg(any(feature = "pext", test))]
+pub mod pext;
+pub mod pgn;
+pub mod pieces;
+pub mod pin;
+pub mod polyglot;
+pub mod search;
+pub mod see;
+pub mod sliding_pieces;
+pub mod tt;
+pub mod uci;
+pub mod zobrist;
+// mod square;
+// #[warn(unused, dead_code)]
+// use once_cell::sync::Lazy;
+
+use crate::bitboard::*;
+use crate::lookup::*;
+// use crate::square::*;
+
+pub const KING_LOOKUP: [BitBoard; 64] = generate_king_lookup();
+pub const QUEEN_LOOKUP: [BitBoard; 64] = generate_queen_lookup();
+pub const ROOK_LOOKUP: [BitBoard; 64] = generate_rook_lookup();
+pub const BISHOP_LOOKUP: [BitBoard; 64] = generate_bishop_lookup();
+pub const KNIGHT_LOOKUP: [BitBoard; 64] = generate_knight_lookup();
+pub const WHITE_PAWN_LOOKUP: [BitBoard; 64] = generate_white_pawn_lookup();
+pub const BLACK_PAWN_LOOKUP: [BitBoard; 64] = generate_black_pawn_lookup();
+// static SLIDE_LOOKUP: Lazy<HashMap<BitBoard, BitBoard>> = Lazy::new(|| {
+//     let hm = generate_slide_lookup();
+//     hm
+// });
+// struct Evaluation {
+//     best_move: Move,
+//     score: f32,
+// }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceType {
+    King,
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+    Pawn,
+}
+impl PieceType {
+    // centipawns, the king is never traded
+    pub const fn value(self) -> i32 {
+        match self {
+            PieceType::King => 0,
+            PieceType::Queen => 900,
+            PieceType::Rook => 500,
+            PieceType::Bishop => 330,
+            PieceType::Knight => 320,
+            PieceType::Pawn => 100,
+        }
+    }
+}
@@ -44,7 +44,7 @@ const fn queen_lookup(piece_index: usize) -> BitBoard {
             };
             multiplier += 1;
         }
-        multiplier = 0;
+        multiplier = 1;
         m += 1;
     }
     BitBoard(result)
@@ -65,7 +65,7 @@ const fn rook_lookup(piece_index: usize) -> BitBoard {
             };
             multiplier += 1;
         }
-        multiplier = 0;
+        multiplier = 1;
         m += 1;
     }
     BitBoard(result)
@@ -86,7 +86,7 @@ const fn bishop_lookup(piece_index: usize) -> BitBoard {
             };
             multiplier += 1;
         }
-        multiplier = 0;
+        multiplier = 1;
         m += 1;
     }
     BitBoard(result)
@@ -0,0 +1,350 @@
+use once_cell::sync::Lazy;
+
+use crate::bitboard::BitBoard;
+use crate::sliding_pieces::{bishop_fill, rook_fill, SliderAttacks};
+use crate::{BISHOP_LOOKUP, ROOK_LOOKUP};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Magic {
+    pub mask: u64,
+    pub magic: u64,
+    pub shift: u32,
+    pub offset: usize,
+}
+impl Magic {
+    pub const fn index(&self, occupied: BitBoard) -> usize {
+        self.offset + ((occupied.0 & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+pub struct MagicTable {
+    pub rook: [Magic; 64],
+    pub bishop: [Magic; 64],
+    attacks: Vec<BitBoard>,
+}
+
+// found by generate_magics(MAGIC_SEED)
+pub const MAGIC_SEED: u64 = 0x6a09e667f3bcc908;
+pub const ROOK_MAGICS: [u64; 64] = [
+    0x0180022040081080,
+    0x0440022000100040,
+    0x0200102082000840,
+    0x29001001002048c4,
+    0x0600020044100820,
+    0x1500090028140006,
+    0x4480020000802100,
+    0x020006002100884c,
+    0x0006002880410200,
+    0x5400808020004000,
+    0x0011001242200100,
+    0x0000801000080084,
+    0x0020808004000800,
+    0x00a1000300080400,
+    0x3009000401002200,
+    0x00060002008c0841,
+    0x04c0008000402084,
+    0x2120004000300044,
+    0x0000110020010043,
+    0x0000818010004800,
+    0x0400310014580100,
+    0x10080801044010a0,
+    0x02a0040008029001,
+    0x8010820001c09704,
+    0x0020400080008024,
+    0x0560002080400080,
+    0x0000100080200081,
+    0x0008100080080084,
+    0x8000080080040080,
+    0x2012000200041008,
+    0x0080028400100108,
+    0x0084008200010854,
+    0x4040400020800080,
+    0x4000200048401000,
+    0x1010002801200400,
+    0x0038021002800882,
+    0xd002002006001008,
+    0x0020020080800400,
+    0x0060800100800200,
+    0x080111088200085c,
+    0x0000804000208008,
+    0x8800400500890020,
+    0x8400200010008080,
+    0x1000080010008080,
+    0x0000080100050010,
+    0x2004040002008080,
+    0x0084287002040001,
+    0x2288a88108520004,
+    0x0280008100225500,
+    0xc002400082200280,
+    0x0000200080100080,
+    0x0200100100200900,
+    0x0004000800048080,
+    0x80e4020080040080,
+    0x0002000804010200,
+    0x8000540108408a00,
+    0x0609004022811202,
+    0x0885084000148021,
+    0x14001043000a2001,
+    0x4090100020090105,
+    0x0003001800041007,
+    0x2009000208040001,
+    0x2100021008411084,
+    0x0044004100240082,
+];
+pub const BISHOP_MAGICS: [u64; 64] = [
+    0x4042420408009300,
+    0x4030104080808600,
+    0x202409042900a002,
+    0x4048048700100020,
+    0x40a1104080020480,
+    0x4081100210c00880,
+    0x0180880809040001,
+    0x104083440e014002,
+    0x0082042048020080,
+    0x224404e10c040280,
+    0x0000112800810004,
+    0x0000044048800051,
+    0x00840c1044540000,
+    0x022108c820101028,
+    0x00208c2484342001,
+    0x000202004a121040,
+    0x4310000810102080,
+    0x9095188210021600,
+    0x404810a1080d0010,
+    0x1005100801410000,
+    0x1404008202114000,
+    0x2031040601010101,
+    0x004c001201040380,
+    0x0003200501280200,
+    0x0802100208901010,
+    0x9204231010100100,
+    0x012e010002240400,
+    0x0040040020410020,
+    0x0841001049004000,
+    0x0010002283008800,
+    0x000c028000482484,
+    0x0000830002031080,
+    0x0a01042002122008,
+    0x0401042100300121,
+    0x1001008200204800,
+    0x2401020080080080,
+    0x0120821004020080,
+    0x0044080020861000,
+    0x0001040400010108,
+    0x009822022400848a,
+    0x80060a1086404001,
+    0x01a4010410080201,
+    0x0080201050000804,
+    0x014020c20082080a,
+    0x5011041008800408,
+    0x08a002004200c040,
+    0x0108310810880200,
+    0x04420c2401200890,
+    0x200100821040b019,
+    0x0420908801100000,
+    0x0008020042080950,
+    0x004308018c0c2540,
+    0x0220000810340000,
+    0x0350c04801010484,
+    0x0428901c08085000,
+    0x2402048104010031,
+    0x0003024802080200,
+    0x2010484404010842,
+    0x8064000200420844,
+    0x0200008000840412,
+    0x008080d410a21201,
+    0x1042400a02880200,
+    0x0000840830040088,
+    0x60131010020244c0,
+];
+
+pub static MAGICS: Lazy<MagicTable> = Lazy::new(MagicTable::new);
+
+pub struct MagicAttacks;
+impl SliderAttacks for MagicAttacks {
+    fn rook_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+        MAGICS.attacks[MAGICS.rook[square].index(occupied)]
+    }
+    fn bishop_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+        MAGICS.attacks[MAGICS.bishop[square].index(occupied)]
+    }
+}
+
+impl MagicTable {
+    fn new() -> Self {
+        let mut attacks = vec![];
+        let rook = Self::fill_table(&mut attacks, rook_mask, &ROOK_MAGICS, rook_fill);
+        let bishop = Self::fill_table(&mut attacks, bishop_mask, &BISHOP_MAGICS, bishop_fill);
+        MagicTable {
+            rook,
+            bishop,
+            attacks,
+        }
+    }
+    fn fill_table(
+        attacks: &mut Vec<BitBoard>,
+        mask: fn(usize) -> u64,
+        magics: &[u64; 64],
+        fill: fn(u64, u64) -> u64,
+    ) -> [Magic; 64] {
+        let mut table = [Magic {
+            mask: 0,
+            magic: 0,
+            shift: 0,
+            offset: 0,
+        }; 64];
+        for square in 0..64 {
+            let mask = mask(square);
+            table[square] = Magic {
+                mask,
+                magic: magics[square],
+                shift: 64 - mask.count_ones(),
+                offset: attacks.len(),
+            };
+            attacks.resize(attacks.len() + (1 << mask.count_ones()), BitBoard::EMPTY);
+            for occupied in subsets(mask) {
+                attacks[table[square].index(BitBoard(occupied))] =
+                    BitBoard(fill(1u64 << square, !occupied));
+            }
+        }
+        table
+    }
+}
+
+pub const FILE_A: u64 = 0x0101010101010101;
+pub const FILE_H: u64 = 0x8080808080808080;
+const RANK_1: u64 = 0xff;
+const RANK_8: u64 = 0xff00000000000000;
+
+// blockers on the last square of a ray never change the attack set, so leave them out
+pub const fn rook_mask(square: usize) -> u64 {
+    let square_bb = 1u64 << square;
+    let mut edges = 0;
+    if square_bb & FILE_A == 0 {
+        edges |= FILE_A;
+    }
+    if square_bb & FILE_H == 0 {
+        edges |= FILE_H;
+    }
+    if square_bb & RANK_1 == 0 {
+        edges |= RANK_1;
+    }
+    if square_bb & RANK_8 == 0 {
+        edges |= RANK_8;
+    }
+    ROOK_LOOKUP[square].0 & !edges & !square_bb
+}
+pub const fn bishop_mask(square: usize) -> u64 {
+    BISHOP_LOOKUP[square].0 & !(FILE_A | FILE_H | RANK_1 | RANK_8) & !(1u64 << square)
+}
+
+// every subset of mask, by the carry-rippler trick
+pub fn subsets(mask: u64) -> Vec<u64> {
+    let mut subsets = vec![];
+    let mut subset = 0u64;
+    loop {
+        subsets.push(subset);
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            return subsets;
+        }
+    }
+}
+
+pub struct Xorshift(pub u64);
+impl Xorshift {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+    // magics with few set bits collide far less often
+    pub fn sparse(&mut self) -> u64 {
+        self.next_u64() & self.next_u64() & self.next_u64()
+    }
+}
+
+pub fn find_magic(square: usize, rook: bool, rng: &mut Xorshift) -> u64 {
+    let (mask, fill): (u64, fn(u64, u64) -> u64) = match rook {
+        true => (rook_mask(square), rook_fill),
+        false => (bishop_mask(square), bishop_fill),
+    };
+    let shift = 64 - mask.count_ones();
+    let occupancies = subsets(mask);
+    let attacks: Vec<u64> = occupancies
+        .iter()
+        .map(|occupied| fill(1u64 << square, !occupied))
+        .collect();
+    let mut table = vec![0u64; occupancies.len()];
+    let mut epoch = vec![0u32; occupancies.len()];
+    let mut attempt = 0;
+    loop {
+        let magic = rng.sparse();
+        if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+        attempt += 1;
+        let collision = occupancies.iter().zip(&attacks).any(|(occupied, &attack)| {
+            let index = (occupied.wrapping_mul(magic) >> shift) as usize;
+            if epoch[index] < attempt {
+                epoch[index] = attempt;
+                table[index] = attack;
+                false
+            } else {
+                table[index] != attack
+            }
+        });
+        if !collision {
+            return magic;
+        }
+    }
+}
+
+pub fn generate_magics(seed: u64) -> ([u64; 64], [u64; 64]) {
+    let mut rng = Xorshift(seed);
+    let mut rook = [0u64; 64];
+    let mut bishop = [0u64; 64];
+    for (square, magic) in rook.iter_mut().enumerate() {
+        *magic = find_magic(square, true, &mut rng);
+    }
+    for (square, magic) in bishop.iter_mut().enumerate() {
+        *magic = find_magic(square, false, &mut rng);
+    }
+    (rook, bishop)
+}
+
+#[test]
+fn magics_are_reproducible() {
+    assert_eq!(generate_magics(MAGIC_SEED), (ROOK_MAGICS, BISHOP_MAGICS));
+}
+#[test]
+fn magic_attacks_match_fill() {
+    let mut rng = Xorshift(0xdeadbeef);
+    for square in 0..64 {
+        for _ in 0..1000 {
+            let occupied = rng.next_u64() & rng.next_u64();
+            let empty = !occupied;
+            assert_eq!(
+                MagicAttacks::rook_attacks(square, BitBoard(occupied)).0,
+                rook_fill(1 << square, empty)
+            );
+            assert_eq!(
+                MagicAttacks::bishop_attacks(square, BitBoard(occupied)).0,
+                bishop_fill(1 << square, empty)
+            );
+        }
+        for occupied in subsets(rook_mask(square)) {
+            assert_eq!(
+                MagicAttacks::rook_attacks(square, BitBoard(occupied)).0,
+                rook_fill(1 << square, !occupied)
+            );
+        }
+        for occupied in subsets(bishop_mask(square)) {
+            assert_eq!(
+                MagicAttacks::bishop_attacks(square, BitBoard(occupied)).0,
+                bishop_fill(1 << square, !occupied)
+            );
+        }
+    }
+}
@@ -1,55 +1,3 @@
-use std::{collections::HashMap, vec};
-
-mod bitboard;
-mod board;
-mod color;
-mod gamestate;
-mod lookup;
-mod moves;
-mod pieces;
-mod pin;
-mod sliding_pieces;
-// mod square;
-// #[warn(unused, dead_code)]
-// use once_cell::sync::Lazy;
-use std::time::SystemTime;
-
-use crate::bitboard::*;
-use crate::board::*;
-use crate::color::*;
-use crate::gamestate::*;
-use crate::lookup::*;
-use crate::moves::*;
-use crate::pin::*;
-use crate::sliding_pieces::*;
-// use crate::square::*;
-
-pub const KING_LOOKUP: [BitBoard; 64] = generate_king_lookup();
-pub const QUEEN_LOOKUP: [BitBoard; 64] = generate_queen_lookup();
-pub const ROOK_LOOKUP: [BitBoard; 64] = generate_rook_lookup();
-pub const BISHOP_LOOKUP: [BitBoard; 64] = generate_bishop_lookup();
-pub const KNIGHT_LOOKUP: [BitBoard; 64] = generate_knight_lookup();
-pub const WHITE_PAWN_LOOKUP: [BitBoard; 64] = generate_white_pawn_lookup();
-pub const BLACK_PAWN_LOOKUP: [BitBoard; 64] = generate_black_pawn_lookup();
-// static SLIDE_LOOKUP: Lazy<HashMap<BitBoard, BitBoard>> = Lazy::new(|| {
-//     let hm = generate_slide_lookup();
-//     hm
-// });
-// struct Evaluation {
-//     best_move: Move,
-//     score: f32,
-// }
-
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum PieceType {
-    King,
-    Queen,
-    Rook,
-    Bishop,
-    Knight,
-    Pawn,
-}
-
 // #[derive(Debug, Clone, Copy)]
 // pub enum PromotionType {
 //     Queen,
@@ -1554,45 +1502,6 @@ pub enum PieceType {
 //     //     // }
 //     //     todo!()
 //     // }
-//     fn negamax(&self, depth: usize, color: Color) -> (Move, f32) {
-//         let mut best_score = f32::NEG_INFINITY;
-//         let mut best_move = self.moves(color)[0];
-//         if depth == 0 {
-//             return (best_move, self.static_eval());
-//         }
-//         for piece_move in self.moves(color) {
-//             let new_gamestate = self.apply_move(piece_move);
-//             let (_, score) = new_gamestate.negamax(depth - 1, !new_gamestate.active_color);
-//             if -score > best_score {
-//                 best_score = -score;
-//                 best_move = piece_move;
-//             }
-//         }
-//         (best_move, best_score)
-//     }
-//     fn static_eval(&self) -> f32 {
-//         let score: f32;
-//         let king_diff = (self.pieces.w_king.count_ones() - self.pieces.b_king.count_ones()) as f32;
-//         let queen_diff =
-//             (self.pieces.w_queen.count_ones() - self.pieces.b_queen.count_ones()) as f32;
-//         let rook_diff = (self.pieces.w_rook.count_ones() - self.pieces.b_rook.count_ones()) as f32;
-//         let bishop_diff =
-//             (self.pieces.w_bishop.count_ones() - self.pieces.b_bishop.count_ones()) as f32;
-//         let knight_diff =
-//             (self.pieces.w_knight.count_ones() - self.pieces.b_knight.count_ones()) as f32;
-//         let pawn_diff = (self.pieces.w_pawn.count_ones() - self.pieces.b_pawn.count_ones()) as f32;
-//         let side = match self.active_color {
-//             Color::White => 1.0,
-//             Color::Black => -1.0,
-//         };
-//         score = 69420.0 * king_diff
-//             + 9.0 * queen_diff
-//             + 5.0 * rook_diff
-//             + 3.0 * bishop_diff
-//             + 3.0 * knight_diff
-//             + pawn_diff;
-//         score * side
-//     }
 // }
 
 // // pub fn piece_lookup(piece_index: usize, piece_type: PieceType, piece_color: Option<Color>) -> u64 {
@@ -1719,33 +1628,8 @@ pub enum PieceType {
 // //     bitboard
 // // }
 fn main() {
-    let board = "rnb1kbnr/pppppppp/3r2q1/8/3Q4/3K4/PPPPPPPP/RNBQ1BNR w HAkq - 0 1"
-        .parse::<Board>()
-        .ok()
-        .unwrap();
-    let now = SystemTime::now();
-    let (checkers, pinners) = board.checkers_pinners(board.side_to_move);
-    let (checkmask, pinmask) = board.checkmask_pinmask(board.side_to_move, checkers, pinners);
-    let since = now.elapsed().expect("time");
-    println!("cm {}", pinners.0);
-    println!("pm {:?}", pinmask);
-    println!(
-        "Time: {}ms {}µs {}ns",
-        since.as_millis(),
-        since.as_micros(),
-        since.as_nanos()
-    );
-    // println!(
-    //     "e {}",
-    //     between_rays(BitBoard(1u64 << 19), BitBoard(1u64 << 46)).0
-    // );
-    // println!("{}", );
+    bitboard_generator::uci::run();
 }
-//notes
-//undefended_pieces = white_pieces - (white_space & white_pieces)
-//award +-0.5 for the bishop pair
-
 //todo
 //fix movegen bugs
-//fix negamax not working
 //regenerate masks  each time a move is made
@@ -1,11 +1,199 @@
-use std::time::SystemTime;
-
 use crate::{bitboard::BitBoard, PieceType};
 
-pub struct Move {
-    from: usize,
-    to: usize,
-    promotion: Option<PieceType>,
+// what kind of move it is, in the top four bits
+const NORMAL: u16 = 0;
+const DOUBLE_PUSH: u16 = 1;
+const CASTLING: u16 = 2;
+const EN_PASSANT: u16 = 3;
+// knight, bishop, rook and queen promotions follow in that order
+const PROMOTION: u16 = 4;
+
+// from in bits 0-5, to in bits 6-11, the kind in bits 12-15
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+impl Move {
+    /// No move at all, from a1 to a1. UCI writes it as "0000".
+    pub const NULL: Move = Move(0);
+
+    const fn with_kind(from: usize, to: usize, kind: u16) -> Self {
+        Move(from as u16 | (to as u16) << 6 | kind << 12)
+    }
+    pub const fn new(from: usize, to: usize, promotion: Option<PieceType>) -> Self {
+        let kind = match promotion {
+            None => NORMAL,
+            Some(PieceType::Knight) => PROMOTION,
+            Some(PieceType::Bishop) => PROMOTION + 1,
+            Some(PieceType::Rook) => PROMOTION + 2,
+            Some(PieceType::Queen) => PROMOTION + 3,
+            Some(PieceType::King | PieceType::Pawn) => {
+                panic!("pawns can't promote to kings or pawns")
+            }
+        };
+        Move::with_kind(from, to, kind)
+    }
+    pub const fn double_push(from: usize, to: usize) -> Self {
+        Move::with_kind(from, to, DOUBLE_PUSH)
+    }
+    // the king's destination in standard chess, the rook's square in chess960
+    pub const fn castling(from: usize, to: usize) -> Self {
+        Move::with_kind(from, to, CASTLING)
+    }
+    pub const fn en_passant(from: usize, to: usize) -> Self {
+        Move::with_kind(from, to, EN_PASSANT)
+    }
+    pub const fn from(&self) -> usize {
+        (self.0 & 63) as usize
+    }
+    pub const fn to(&self) -> usize {
+        (self.0 >> 6 & 63) as usize
+    }
+    pub const fn promotion(&self) -> Option<PieceType> {
+        match self.0 >> 12 {
+            4 => Some(PieceType::Knight),
+            5 => Some(PieceType::Bishop),
+            6 => Some(PieceType::Rook),
+            7 => Some(PieceType::Queen),
+            _ => None,
+        }
+    }
+    pub const fn is_double_push(&self) -> bool {
+        self.0 >> 12 == DOUBLE_PUSH
+    }
+    pub const fn is_castling(&self) -> bool {
+        self.0 >> 12 == CASTLING
+    }
+    pub const fn is_en_passant(&self) -> bool {
+        self.0 >> 12 == EN_PASSANT
+    }
+    pub const fn is_null(&self) -> bool {
+        self.0 == 0
+    }
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+    // None for kinds no move has
+    pub const fn from_bits(bits: u16) -> Option<Self> {
+        match bits >> 12 {
+            0..=7 => Some(Move(bits)),
+            _ => None,
+        }
+    }
+}
+impl core::fmt::Display for Move {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_null() {
+            return write!(f, "0000");
+        }
+        for square in [self.from(), self.to()] {
+            let file = (b'a' + (square & 7) as u8) as char;
+            let rank = (b'1' + (square >> 3) as u8) as char;
+            write!(f, "{}{}", file, rank)?;
+        }
+        match self.promotion() {
+            Some(PieceType::Queen) => write!(f, "q"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Knight) => write!(f, "n"),
+            _ => Ok(()),
+        }
+    }
+}
+impl core::fmt::Debug for Move {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Move({})", self)
+    }
+}
+
+pub const MAX_MOVES: usize = 256;
+
+// a fixed array instead of a Vec, no position has more than 218 legal moves
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+impl MoveList {
+    pub const fn new() -> Self {
+        MoveList {
+            moves: [Move::NULL; MAX_MOVES],
+            len: 0,
+        }
+    }
+    pub fn push(&mut self, piece_move: Move) {
+        self.moves[self.len] = piece_move;
+        self.len += 1;
+    }
+    pub fn retain(&mut self, mut keep: impl FnMut(&Move) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.len {
+            if keep(&self.moves[i]) {
+                self.moves[kept] = self.moves[i];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+impl Default for MoveList {
+    fn default() -> Self {
+        MoveList::new()
+    }
+}
+impl core::ops::Deref for MoveList {
+    type Target = [Move];
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+impl core::ops::DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = core::iter::Take<core::array::IntoIter<Move, MAX_MOVES>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.into_iter().take(self.len)
+    }
+}
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = core::slice::Iter<'a, Move>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl PartialEq for MoveList {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+impl Eq for MoveList {}
+impl core::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// which legal moves to generate, captures and quiets split every position's moves
+// between them, promotions going with the captures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenMode {
+    All,
+    Captures,
+    Quiets,
+    // every legal move when in check, nothing otherwise
+    Evasions,
+    QuietChecks,
+}
+impl GenMode {
+    pub const fn captures(self) -> bool {
+        matches!(self, GenMode::All | GenMode::Evasions | GenMode::Captures)
+    }
+    pub const fn quiets(self) -> bool {
+        !matches!(self, GenMode::Captures)
+    }
 }
 
 pub const fn between_rays(from: BitBoard, to: BitBoard) -> BitBoard {
@@ -29,7 +217,7 @@ pub const fn between_rays(from: BitBoard, to: BitBoard) -> BitBoard {
         between
     }
     // panic!("e!");
-    const TABLE: [[BitBoard; 64]; 64] = {
+    static TABLE: [[BitBoard; 64]; 64] = {
         let mut from = 0usize;
         let mut to = 0usize;
         let mut table: [[BitBoard; 64]; 64] = [[BitBoard::EMPTY; 64]; 64];
@@ -49,10 +237,10 @@ pub const fn between_rays(from: BitBoard, to: BitBoard) -> BitBoard {
 #[test]
 fn time_table() {
     let between = between_rays(BitBoard(1), BitBoard(1 << 63));
-    let between = between_rays(BitBoard(1), BitBoard(1 << 63));
-    let between = between_rays(BitBoard(1), BitBoard(1 << 63));
-    let start = SystemTime::now();
-    let between = between_rays(BitBoard(1), BitBoard(1 << 63));
-    let since = start.elapsed().expect(".").as_nanos();
-    print!("{}ns", since);
+    assert_eq!(between.0, 0x0040201008040200);
+}
+#[test]
+#[should_panic]
+fn promotion_to_king() {
+    Move::new(52, 60, Some(PieceType::King));
 }
@@ -0,0 +1,334 @@
+use std::fmt;
+
+use crate::fen::parse_square;
+use crate::{board::Board, moves::Move, PieceType};
+
+const fn piece_letter(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::King => 'K',
+        PieceType::Queen => 'Q',
+        PieceType::Rook => 'R',
+        PieceType::Bishop => 'B',
+        PieceType::Knight => 'N',
+        PieceType::Pawn => 'P',
+    }
+}
+fn promotion_piece(letter: char) -> Option<PieceType> {
+    match letter.to_ascii_uppercase() {
+        'Q' => Some(PieceType::Queen),
+        'R' => Some(PieceType::Rook),
+        'B' => Some(PieceType::Bishop),
+        'N' => Some(PieceType::Knight),
+        _ => None,
+    }
+}
+fn square_name(index: usize) -> String {
+    let file = (b'a' + (index & 7) as u8) as char;
+    let rank = (b'1' + (index >> 3) as u8) as char;
+    format!("{}{}", file, rank)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveParseError {
+    // not a move in this notation at all
+    BadSyntax(String),
+    // well formed, but not one of the legal moves here
+    IllegalMove(String),
+    // SAN that fits more than one legal move
+    AmbiguousMove(String),
+}
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveParseError::BadSyntax(text) => write!(f, "invalid move '{}'", text),
+            MoveParseError::IllegalMove(text) => write!(f, "illegal move '{}'", text),
+            MoveParseError::AmbiguousMove(text) => write!(f, "ambiguous move '{}'", text),
+        }
+    }
+}
+impl std::error::Error for MoveParseError {}
+
+impl Move {
+    // chess960 castling moves already point at the rook, so Display is the UCI form
+    pub fn to_uci(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Board {
+    /// Parses a move in UCI's long algebraic notation ("e2e4", "e7e8q")
+    /// and checks it against the legal moves in this position.
+    pub fn parse_uci_move(&self, text: &str) -> Result<Move, MoveParseError> {
+        let bad_syntax = || MoveParseError::BadSyntax(text.to_owned());
+        let (from, to) = match (text.get(0..2), text.get(2..4)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Err(bad_syntax()),
+        };
+        let (from, to) = (
+            parse_square(from).ok_or_else(bad_syntax)?,
+            parse_square(to).ok_or_else(bad_syntax)?,
+        );
+        let promotion = match &text[4..] {
+            "" => None,
+            "q" => Some(PieceType::Queen),
+            "r" => Some(PieceType::Rook),
+            "b" => Some(PieceType::Bishop),
+            "n" => Some(PieceType::Knight),
+            _ => return Err(bad_syntax()),
+        };
+        self.find_move(from, to, promotion)
+            .ok_or_else(|| MoveParseError::IllegalMove(text.to_owned()))
+    }
+    /// Standard algebraic notation for a legal move, "Nbd2", "exd6", "e8=Q+", "O-O-O#".
+    pub fn to_san(&self, piece_move: Move) -> String {
+        let (from, to) = (piece_move.from(), piece_move.to());
+        let mut san = String::new();
+        if piece_move.is_castling() {
+            san.push_str(if to > from { "O-O" } else { "O-O-O" });
+        } else {
+            let piece_type = self.piece_on(from).expect("no piece to move");
+            let capture = self.captured(piece_move).is_some();
+            if piece_type == PieceType::Pawn {
+                if capture {
+                    san.push((b'a' + (from & 7) as u8) as char);
+                }
+            } else {
+                san.push(piece_letter(piece_type));
+                // the other pieces of the same kind that could go there too
+                let rivals: Vec<usize> = self
+                    .legal_moves()
+                    .into_iter()
+                    .filter(|other| {
+                        other.to() == to
+                            && other.from() != from
+                            && self.piece_on(other.from()) == Some(piece_type)
+                            && !other.is_castling()
+                    })
+                    .map(|other| other.from())
+                    .collect();
+                let name = square_name(from);
+                let same_file = rivals.iter().any(|&rival| rival & 7 == from & 7);
+                let same_rank = rivals.iter().any(|&rival| rival >> 3 == from >> 3);
+                if !rivals.is_empty() {
+                    san.push_str(match (same_file, same_rank) {
+                        (false, _) => &name[..1],
+                        (true, false) => &name[1..],
+                        (true, true) => &name,
+                    });
+                }
+            }
+            if capture {
+                san.push('x');
+            }
+            san.push_str(&square_name(to));
+            if let Some(promotion) = piece_move.promotion() {
+                san.push('=');
+                san.push(piece_letter(promotion));
+            }
+        }
+        let mut after = self.clone();
+        after.make_move(piece_move);
+        if after.is_checkmate() {
+            san.push('#');
+        } else if after.is_check() {
+            san.push('+');
+        }
+        san
+    }
+    /// Parses standard algebraic notation against the legal moves in this position.
+    /// Castling may be written with zeros, check and mate marks and annotations
+    /// are optional, and promotion pieces may be lowercase or lack the '='.
+    pub fn parse_san(&self, text: &str) -> Result<Move, MoveParseError> {
+        let bad_syntax = || MoveParseError::BadSyntax(text.to_owned());
+        let san = text.trim_end_matches(['+', '#', '!', '?']);
+        let legal_moves = self.legal_moves();
+        let castling = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        let candidates: Vec<Move> = if let Some(kingside) = castling {
+            legal_moves
+                .into_iter()
+                .filter(|&piece_move| {
+                    piece_move.is_castling() && (piece_move.to() > piece_move.from()) == kingside
+                })
+                .collect()
+        } else {
+            let mut chars: Vec<char> = san.chars().collect();
+            // "e8=Q", "e8=q" and "e8Q" all promote, but "Bb8" only moves a bishop
+            let mut promotion = None;
+            if let [.., '1' | '8', '=', letter] | [.., '1' | '8', letter] = chars[..] {
+                if let Some(piece_type) = promotion_piece(letter) {
+                    promotion = Some(piece_type);
+                    let len = chars.len() - if chars[chars.len() - 2] == '=' { 2 } else { 1 };
+                    chars.truncate(len);
+                }
+            }
+            let piece_type = match chars.first() {
+                Some('K') => PieceType::King,
+                Some('Q') => PieceType::Queen,
+                Some('R') => PieceType::Rook,
+                Some('B') => PieceType::Bishop,
+                Some('N') => PieceType::Knight,
+                Some('a'..='h') => PieceType::Pawn,
+                _ => return Err(bad_syntax()),
+            };
+            if piece_type != PieceType::Pawn {
+                chars.remove(0);
+            }
+            if chars.len() < 2 {
+                return Err(bad_syntax());
+            }
+            let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+            let to = parse_square(&destination).ok_or_else(bad_syntax)?;
+            if chars.last() == Some(&'x') {
+                chars.pop();
+            }
+            // whatever is left says which of several pieces moves
+            let (mut file, mut rank) = (None, None);
+            for c in chars {
+                match c {
+                    'a'..='h' if file.is_none() && rank.is_none() => {
+                        file = Some((c as u8 - b'a') as usize)
+                    }
+                    '1'..='8' if rank.is_none() => rank = Some((c as u8 - b'1') as usize),
+                    _ => return Err(bad_syntax()),
+                }
+            }
+            legal_moves
+                .into_iter()
+                .filter(|&piece_move| {
+                    let from = piece_move.from();
+                    piece_move.to() == to
+                        && piece_move.promotion() == promotion
+                        && self.piece_on(from) == Some(piece_type)
+                        && !piece_move.is_castling()
+                        && file.is_none_or(|file| from & 7 == file)
+                        && rank.is_none_or(|rank| from >> 3 == rank)
+                })
+                .collect()
+        };
+        match candidates[..] {
+            [piece_move] => Ok(piece_move),
+            [] => Err(MoveParseError::IllegalMove(text.to_owned())),
+            _ => Err(MoveParseError::AmbiguousMove(text.to_owned())),
+        }
+    }
+}
+
+#[test]
+fn uci_moves() {
+    let board = Board::default();
+    let e4 = board.parse_uci_move("e2e4").unwrap();
+    assert_eq!(e4, Move::double_push(12, 28));
+    assert_eq!(e4.to_uci(), "e2e4");
+    let board = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1".parse::<Board>().unwrap();
+    let promotion = board.parse_uci_move("b7b8q").unwrap();
+    assert_eq!(promotion.promotion(), Some(PieceType::Queen));
+    assert_eq!(promotion.to_uci(), "b7b8q");
+    assert_eq!(
+        board.parse_uci_move("b7b8").unwrap_err(),
+        MoveParseError::IllegalMove("b7b8".to_owned())
+    );
+    for text in ["", "e2", "e2e9", "i2e4", "e2e4k", "b7b8qq"] {
+        assert_eq!(
+            board.parse_uci_move(text),
+            Err(MoveParseError::BadSyntax(text.to_owned()))
+        );
+    }
+    // standard castling names the king's square, chess960 the rook's
+    let board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"
+        .parse::<Board>()
+        .unwrap();
+    assert_eq!(board.parse_uci_move("e1g1").unwrap().to_uci(), "e1g1");
+    assert!(board.parse_uci_move("e1h1").is_err());
+    let board = "4k3/8/8/8/8/8/8/1R2K1R1 w GB - 0 1"
+        .parse::<Board>()
+        .unwrap();
+    assert_eq!(board.parse_uci_move("e1g1").unwrap().to_uci(), "e1g1");
+    assert_eq!(board.parse_uci_move("e1b1").unwrap().to_uci(), "e1b1");
+    assert!(board.parse_uci_move("e1c1").is_err());
+}
+#[test]
+fn san_output() {
+    let san = |fen: &str, uci: &str| {
+        let board = fen.parse::<Board>().unwrap();
+        board.to_san(board.parse_uci_move(uci).unwrap())
+    };
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san(start, "e2e4"), "e4");
+    assert_eq!(san(start, "g1f3"), "Nf3");
+    // file, rank, and both
+    assert_eq!(san("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "b1d2"), "Nbd2");
+    assert_eq!(san("4k3/8/8/8/8/R7/8/R3K3 w - - 0 1", "a1a2"), "R1a2");
+    assert_eq!(san("8/8/k7/8/4Q2Q/8/8/K6Q w - - 0 1", "h4e1"), "Qh4e1");
+    // a pinned rival doesn't count
+    assert_eq!(san("k3r3/8/8/8/8/8/4N3/1N2K3 w - - 0 1", "b1c3"), "Nc3");
+    assert_eq!(
+        san(
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            "e4d5"
+        ),
+        "exd5"
+    );
+    assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+    assert_eq!(san("3rk3/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7d8q"), "exd8=Q+");
+    assert_eq!(san("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8n"), "b8=N");
+    assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"), "O-O");
+    assert_eq!(san("r3k2r/8/8/8/8/8/8/3K4 b kq - 0 1", "e8c8"), "O-O-O+");
+    assert_eq!(san("4k3/8/8/8/8/8/8/1R2K1R1 w GB - 0 1", "e1b1"), "O-O-O");
+    assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+}
+#[test]
+fn san_input() {
+    let board = Board::default();
+    assert_eq!(board.parse_san("Nf3"), board.parse_uci_move("g1f3"));
+    assert_eq!(board.parse_san("e4"), board.parse_uci_move("e2e4"));
+    assert_eq!(
+        board.parse_san("e5"),
+        Err(MoveParseError::IllegalMove("e5".to_owned()))
+    );
+    for text in ["", "Z4", "Nf", "Nf9", "e4=", "N1b1c3"] {
+        assert_eq!(
+            board.parse_san(text),
+            Err(MoveParseError::BadSyntax(text.to_owned())),
+            "{}",
+            text
+        );
+    }
+    let board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"
+        .parse::<Board>()
+        .unwrap();
+    for text in ["O-O", "0-0", "O-O+", "O-O!?"] {
+        assert_eq!(board.parse_san(text), board.parse_uci_move("e1g1"));
+    }
+    assert_eq!(board.parse_san("0-0-0"), board.parse_uci_move("e1c1"));
+    let board = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1".parse::<Board>().unwrap();
+    assert_eq!(
+        board.parse_san("Nd2"),
+        Err(MoveParseError::AmbiguousMove("Nd2".to_owned()))
+    );
+    assert_eq!(board.parse_san("Nbd2"), board.parse_uci_move("b1d2"));
+    assert_eq!(board.parse_san("Nb1d2"), board.parse_uci_move("b1d2"));
+    assert_eq!(board.parse_san("Nf1xd2"), board.parse_uci_move("f1d2"));
+    let board = "3rk3/4P3/8/8/8/8/8/4K3 w - - 0 1".parse::<Board>().unwrap();
+    for text in ["exd8=Q+", "exd8=Q", "exd8=q", "exd8Q", "ed8q"] {
+        assert_eq!(
+            board.parse_san(text),
+            board.parse_uci_move("e7d8q"),
+            "{}",
+            text
+        );
+    }
+    let board = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1".parse::<Board>().unwrap();
+    assert_eq!(board.parse_san("b8=N"), board.parse_uci_move("b7b8n"));
+    assert!(board.parse_san("b8").is_err());
+    // every move a position has survives the round trip
+    let board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+        .parse::<Board>()
+        .unwrap();
+    for piece_move in board.legal_moves() {
+        assert_eq!(board.parse_san(&board.to_san(piece_move)), Ok(piece_move));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::bitboard::LIGHT_SQUARES;
+use crate::{board::Board, color::Color};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    ThreefoldRepetition,
+    // a hundred halfmoves without a capture or pawn move, either player may claim it
+    FiftyMoveRule,
+    // a hundred and fifty, the game is over whether anyone claims it or not
+    SeventyFiveMoveRule,
+    InsufficientMaterial,
+    Stalemate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Draw(DrawReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw(DrawReason),
+}
+
+impl Board {
+    pub fn is_check(&self) -> bool {
+        let (checkers, _) = self.checkers_pinners(self.side_to_move);
+        checkers.count() > 0
+    }
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && self.legal_moves().is_empty()
+    }
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_check() && self.legal_moves().is_empty()
+    }
+    // how often the current position was seen before, capture and pawn moves
+    // can't be undone so there is no need to look further back than the last one
+    pub fn repetitions(&self) -> usize {
+        self.history
+            .iter()
+            .rev()
+            .take(self.halfmoves)
+            .skip(1)
+            .step_by(2)
+            .filter(|&&hash| hash == self.hash)
+            .count()
+    }
+    // neither side has the pieces to ever deliver mate
+    pub fn insufficient_material(&self) -> bool {
+        let pieces = &self.pieces;
+        if (pieces.pawn.0 | pieces.rook.0 | pieces.queen.0) > 0 {
+            return false;
+        }
+        let minors = pieces.knight.0 | pieces.bishop.0;
+        // a lone minor, or bishops that all share one square color
+        minors.count_ones() <= 1
+            || pieces.knight.0 == 0
+                && (pieces.bishop.0 & LIGHT_SQUARES == 0 || pieces.bishop.0 & !LIGHT_SQUARES == 0)
+    }
+    /// A draw the rules enforce or let either player claim in this position.
+    pub fn outcome(&self) -> Option<Outcome> {
+        let reason = if self.insufficient_material() {
+            DrawReason::InsufficientMaterial
+        } else if self.halfmoves >= 150 {
+            DrawReason::SeventyFiveMoveRule
+        } else if self.halfmoves >= 100 {
+            DrawReason::FiftyMoveRule
+        } else if self.repetitions() >= 2 {
+            DrawReason::ThreefoldRepetition
+        } else {
+            return None;
+        };
+        Some(Outcome::Draw(reason))
+    }
+    /// How the game stands if it ends here, mate and stalemate first,
+    /// then the draws `outcome` reports. None while play goes on.
+    pub fn result(&self) -> Option<GameResult> {
+        if self.legal_moves().is_empty() {
+            return Some(match (self.is_check(), self.side_to_move) {
+                (false, _) => GameResult::Draw(DrawReason::Stalemate),
+                (true, Color::White) => GameResult::BlackWins,
+                (true, Color::Black) => GameResult::WhiteWins,
+            });
+        }
+        self.outcome()
+            .map(|Outcome::Draw(reason)| GameResult::Draw(reason))
+    }
+}
+
+#[cfg(test)]
+fn play(board: &mut Board, moves: &str) {
+    for text in moves.split_whitespace() {
+        let piece_move = board
+            .legal_moves()
+            .into_iter()
+            .find(|piece_move| piece_move.to_string() == text)
+            .unwrap();
+        board.make_move(piece_move);
+    }
+}
+#[test]
+fn threefold_repetition() {
+    let mut board = Board::default();
+    play(&mut board, "g1f3 g8f6 f3g1 f6g8");
+    assert_eq!(board.repetitions(), 1);
+    assert_eq!(board.outcome(), None);
+    play(&mut board, "g1f3 g8f6 f3g1");
+    assert_eq!(board.outcome(), None);
+    play(&mut board, "f6g8");
+    assert_eq!(board.repetitions(), 2);
+    assert_eq!(
+        board.outcome(),
+        Some(Outcome::Draw(DrawReason::ThreefoldRepetition))
+    );
+    // taking the moves back takes the repetition with them
+    let mut board = Board::default();
+    play(
+        &mut board,
+        "g1f3 g8f6 f3g1 f6g8 e2e4 e7e5 g1f3 g8f6 f3g1 f6g8",
+    );
+    assert_eq!(board.repetitions(), 1);
+}
+#[test]
+fn move_rules() {
+    let board = "4k3/8/8/8/8/8/4P3/4K2R w - - 99 80"
+        .parse::<Board>()
+        .unwrap();
+    assert_eq!(board.outcome(), None);
+    let mut board = board;
+    play(&mut board, "h1h2");
+    assert_eq!(
+        board.outcome(),
+        Some(Outcome::Draw(DrawReason::FiftyMoveRule))
+    );
+    let mut pawn_move = "4k3/8/8/8/8/8/4P3/4K2R w - - 99 80"
+        .parse::<Board>()
+        .unwrap();
+    play(&mut pawn_move, "e2e4");
+    assert_eq!(pawn_move.outcome(), None);
+    let board = "4k3/8/8/8/8/8/4P3/4K2R w - - 150 120"
+        .parse::<Board>()
+        .unwrap();
+    assert_eq!(
+        board.outcome(),
+        Some(Outcome::Draw(DrawReason::SeventyFiveMoveRule))
+    );
+}
+#[test]
+fn insufficient_material() {
+    for (fen, insufficient) in [
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/1N2K3 b - - 0 1", true),
+        // bishops on dark squares only, however many
+        ("4kb2/8/8/8/8/8/8/2B1K1B1 w - - 0 1", true),
+        ("2b1kb2/8/8/8/8/8/8/4K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1", false),
+        ("1n2k3/8/8/8/8/8/8/1N2K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", false),
+    ] {
+        let board = fen.parse::<Board>().unwrap();
+        assert_eq!(board.insufficient_material(), insufficient, "{}", fen);
+    }
+}
+#[test]
+fn terminal_states() {
+    let result = |fen: &str| fen.parse::<Board>().unwrap().result();
+    assert!("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1"
+        .parse::<Board>()
+        .unwrap()
+        .is_check());
+    assert!(!Board::default().is_check());
+    // back rank mate and fool's mate
+    let mated = "R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1"
+        .parse::<Board>()
+        .unwrap();
+    assert!(mated.is_checkmate() && !mated.is_stalemate());
+    assert_eq!(mated.result(), Some(GameResult::WhiteWins));
+    assert_eq!(
+        result("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+        Some(GameResult::BlackWins)
+    );
+    let stalemate = "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".parse::<Board>().unwrap();
+    assert!(stalemate.is_stalemate() && !stalemate.is_checkmate());
+    assert_eq!(
+        stalemate.result(),
+        Some(GameResult::Draw(DrawReason::Stalemate))
+    );
+    // mate on the hundredth halfmove still wins
+    assert_eq!(
+        result("R5k1/5ppp/8/8/8/8/8/6K1 b - - 100 80"),
+        Some(GameResult::WhiteWins)
+    );
+    assert_eq!(
+        result("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"),
+        Some(GameResult::Draw(DrawReason::InsufficientMaterial))
+    );
+    assert_eq!(Board::default().result(), None);
+}
@@ -0,0 +1,250 @@
+use crate::color::Color;
+use crate::magic::{FILE_A, FILE_H};
+use crate::pieces::Pieces;
+
+// (middlegame, endgame) penalties and bonuses, bonuses by rank seen from the pawn's side
+const DOUBLED: (i32, i32) = (-10, -25);
+const ISOLATED: (i32, i32) = (-10, -15);
+const BACKWARD: (i32, i32) = (-8, -12);
+const CONNECTED: [i32; 8] = [0, 3, 5, 8, 14, 24, 40, 0];
+const PASSED_MG: [i32; 8] = [0, 5, 10, 15, 30, 50, 80, 0];
+const PASSED_EG: [i32; 8] = [0, 10, 20, 35, 60, 100, 150, 0];
+const CANDIDATE: [i32; 8] = [0, 3, 5, 10, 18, 30, 0, 0];
+// on top of the passed bonus when nothing stands between the pawn and promotion
+const FREE_PATH_EG: [i32; 8] = [0, 5, 10, 15, 30, 50, 80, 0];
+
+const PAWN_TABLE_SIZE: usize = 1 << 14;
+
+const fn north_fill(mut bb: u64) -> u64 {
+    bb |= bb << 8;
+    bb |= bb << 16;
+    bb | bb << 32
+}
+const fn south_fill(mut bb: u64) -> u64 {
+    bb |= bb >> 8;
+    bb |= bb >> 16;
+    bb | bb >> 32
+}
+// every square in front of the pawns, from their side's point of view
+pub const fn front_span(color: Color, bb: u64) -> u64 {
+    match color {
+        Color::White => north_fill(bb << 8),
+        Color::Black => south_fill(bb >> 8),
+    }
+}
+const fn rear_span(color: Color, bb: u64) -> u64 {
+    match color {
+        Color::White => south_fill(bb >> 8),
+        Color::Black => north_fill(bb << 8),
+    }
+}
+const fn adjacent_files(bb: u64) -> u64 {
+    (bb & !FILE_A) >> 1 | (bb & !FILE_H) << 1
+}
+pub const fn pawn_attacks(color: Color, pawns: u64) -> u64 {
+    match color {
+        Color::White => (pawns & !FILE_A) << 7 | (pawns & !FILE_H) << 9,
+        Color::Black => (pawns & !FILE_A) >> 9 | (pawns & !FILE_H) >> 7,
+    }
+}
+const fn relative_rank(color: Color, index: usize) -> usize {
+    match color {
+        Color::White => index >> 3,
+        Color::Black => 7 - (index >> 3),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PawnEntry {
+    key: u64,
+    pub mg: i32,
+    pub eg: i32,
+    // passed pawns of both colors, their free paths depend on the other pieces
+    pub passed: u64,
+}
+
+// (middlegame, endgame, passed pawns) for `color`
+fn pawn_terms(pieces: &Pieces, color: Color) -> (i32, i32, u64) {
+    let (ours, theirs) = match color {
+        Color::White => (pieces.white.0, pieces.black.0),
+        Color::Black => (pieces.black.0, pieces.white.0),
+    };
+    let (ours, theirs) = (pieces.pawn.0 & ours, pieces.pawn.0 & theirs);
+    let their_attacks = pawn_attacks(!color, theirs);
+    let our_attacks = pawn_attacks(color, ours);
+    let (mut mg, mut eg, mut passed) = (0, 0, 0);
+    let mut pawns = ours;
+    while pawns > 0 {
+        let index = pawns.trailing_zeros() as usize;
+        let pawn = 1u64 << index;
+        let rank = relative_rank(color, index);
+        let file = north_fill(south_fill(pawn));
+        let neighbours = adjacent_files(file);
+        let ahead = front_span(color, pawn);
+        let stop = match color {
+            Color::White => pawn << 8,
+            Color::Black => pawn >> 8,
+        };
+        let rank_mask = 0xffu64 << (index & 56);
+        // own pawns beside or behind, the ones that could ever support this one
+        let supporters = ours & neighbours & (rear_span(color, pawn) | rank_mask);
+        let blocked_by_own = ours & ahead;
+
+        if blocked_by_own > 0 {
+            mg += DOUBLED.0;
+            eg += DOUBLED.1;
+        }
+        if ours & neighbours == 0 {
+            mg += ISOLATED.0;
+            eg += ISOLATED.1;
+        } else if supporters == 0 && their_attacks & stop > 0 {
+            // nothing can come up beside it and the square in front is covered
+            mg += BACKWARD.0;
+            eg += BACKWARD.1;
+        }
+        let phalanx = ours & neighbours & rank_mask;
+        if our_attacks & pawn > 0 || phalanx > 0 {
+            mg += CONNECTED[rank];
+            eg += CONNECTED[rank];
+        }
+        let sentries = theirs & (ahead | adjacent_files(ahead));
+        if sentries == 0 && blocked_by_own == 0 {
+            passed |= pawn;
+            mg += PASSED_MG[rank];
+            eg += PASSED_EG[rank];
+        } else if theirs & ahead == 0
+            && blocked_by_own == 0
+            && supporters.count_ones() >= sentries.count_ones()
+        {
+            // an open file and enough help to force its way through
+            mg += CANDIDATE[rank] / 2;
+            eg += CANDIDATE[rank];
+        }
+        pawns &= pawns - 1;
+    }
+    (mg, eg, passed)
+}
+
+// white minus black
+pub fn pawn_structure(pieces: &Pieces) -> PawnEntry {
+    let (white_mg, white_eg, white_passed) = pawn_terms(pieces, Color::White);
+    let (black_mg, black_eg, black_passed) = pawn_terms(pieces, Color::Black);
+    PawnEntry {
+        key: 0,
+        mg: white_mg - black_mg,
+        eg: white_eg - black_eg,
+        passed: white_passed | black_passed,
+    }
+}
+
+// endgame bonus, white minus black, for passed pawns whose path to promotion is empty
+pub fn free_passers(pieces: &Pieces, passed: u64) -> i32 {
+    let occupied = pieces.white.0 | pieces.black.0;
+    let mut score = 0;
+    for (color, ours, sign) in [
+        (Color::White, pieces.white.0, 1),
+        (Color::Black, pieces.black.0, -1),
+    ] {
+        let mut pawns = passed & ours;
+        while pawns > 0 {
+            let index = pawns.trailing_zeros() as usize;
+            if front_span(color, 1u64 << index) & occupied == 0 {
+                score += sign * FREE_PATH_EG[relative_rank(color, index)];
+            }
+            pawns &= pawns - 1;
+        }
+    }
+    score
+}
+
+// pawn structure keyed by Board::pawn_hash, it only changes on pawn moves and captures
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+}
+impl Default for PawnTable {
+    fn default() -> Self {
+        PawnTable {
+            entries: vec![PawnEntry::default(); PAWN_TABLE_SIZE],
+        }
+    }
+}
+impl PawnTable {
+    pub fn probe(&mut self, pieces: &Pieces, pawn_hash: u64) -> PawnEntry {
+        let slot = &mut self.entries[pawn_hash as usize & (PAWN_TABLE_SIZE - 1)];
+        // the empty entry doubles as the one for no pawns at all
+        if slot.key != pawn_hash || pawn_hash == 0 && pieces.pawn.0 > 0 {
+            *slot = PawnEntry {
+                key: pawn_hash,
+                ..pawn_structure(pieces)
+            };
+        }
+        *slot
+    }
+}
+
+#[cfg(test)]
+fn white_terms(fen: &str) -> (i32, i32, u64) {
+    pawn_terms(&fen.parse::<Pieces>().unwrap(), Color::White)
+}
+#[test]
+fn pawn_structure_terms() {
+    use crate::fen::parse_square;
+    let square = |name: &str| 1u64 << parse_square(name).unwrap();
+    // doubled and isolated on the a file, both pawns pay for the isolation
+    let (mg, eg, passed) = white_terms("4k3/8/8/8/8/P7/P7/4K3 w - - 0 1");
+    assert_eq!(
+        (mg, eg),
+        (
+            DOUBLED.0 + 2 * ISOLATED.0 + PASSED_MG[2],
+            DOUBLED.1 + 2 * ISOLATED.1 + PASSED_EG[2]
+        )
+    );
+    assert_eq!(passed, square("a3"));
+    // d3 can't be supported and c5 covers its stop square, e4 is defended and passed
+    let (mg, _, passed) = white_terms("4k3/8/8/2p5/4P3/3P4/8/4K3 w - - 0 1");
+    assert_eq!(mg, BACKWARD.0 + CONNECTED[3] + PASSED_MG[3]);
+    assert_eq!(passed, square("e4"));
+    // a phalanx on the fifth is connected and passed
+    let (_, eg, passed) = white_terms("4k3/8/8/3PP3/8/8/8/4K3 w - - 0 1");
+    assert_eq!(eg, 2 * (CONNECTED[4] + PASSED_EG[4]));
+    assert_eq!(passed, square("d5") | square("e5"));
+    // two against one on the queenside makes the a pawn a candidate
+    let (_, eg, passed) = white_terms("4k3/1p6/8/8/PP6/8/8/4K3 w - - 0 1");
+    assert_eq!(passed, 0);
+    assert_eq!(eg, CANDIDATE[3] + 2 * CONNECTED[3]);
+}
+#[test]
+fn pawn_structure_symmetry() {
+    use crate::eval::{mirror, EVAL_POSITIONS};
+    for fen in EVAL_POSITIONS {
+        let pieces = fen.parse::<Pieces>().unwrap();
+        let entry = pawn_structure(&pieces);
+        let mirrored = pawn_structure(&mirror(&pieces));
+        assert_eq!(
+            (entry.mg, entry.eg),
+            (-mirrored.mg, -mirrored.eg),
+            "{}",
+            fen
+        );
+        assert_eq!(entry.passed.swap_bytes(), mirrored.passed);
+        assert_eq!(
+            free_passers(&pieces, entry.passed),
+            -free_passers(&mirror(&pieces), mirrored.passed)
+        );
+    }
+}
+#[test]
+fn pawn_table_matches() {
+    use crate::board::Board;
+    let mut table = PawnTable::default();
+    for fen in crate::eval::EVAL_POSITIONS {
+        let board = fen.parse::<Board>().unwrap();
+        for _ in 0..2 {
+            let entry = table.probe(&board.pieces, board.pawn_hash);
+            assert_eq!((entry.mg, entry.eg, entry.passed), {
+                let fresh = pawn_structure(&board.pieces);
+                (fresh.mg, fresh.eg, fresh.passed)
+            });
+        }
+    }
+}
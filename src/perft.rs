@@ -0,0 +1,192 @@
+use crate::board::Board;
+
+impl Board {
+    pub fn perft(&mut self, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for piece_move in moves {
+            let undo = self.make_move(piece_move);
+            nodes += self.perft(depth - 1);
+            self.unmake_move(piece_move, undo);
+        }
+        nodes
+    }
+    // perft split by root move, for diffing against another generator
+    pub fn divide(&mut self, depth: usize) -> u64 {
+        let mut nodes = 0;
+        for piece_move in self.legal_moves() {
+            let undo = self.make_move(piece_move);
+            let count = self.perft(depth.saturating_sub(1));
+            self.unmake_move(piece_move, undo);
+            println!("{}: {}", piece_move, count);
+            nodes += count;
+        }
+        println!("\nNodes searched: {}", nodes);
+        nodes
+    }
+}
+
+#[cfg(test)]
+fn perft_suite(positions: &[(&str, &[u64])]) {
+    for (fen, counts) in positions {
+        let mut board = fen.parse::<Board>().ok().unwrap();
+        for (depth, &count) in counts.iter().enumerate() {
+            assert_eq!(board.perft(depth + 1), count, "{} depth {}", fen, depth + 1);
+        }
+    }
+}
+#[test]
+fn perft_startpos() {
+    perft_suite(&[(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &[20, 400, 8902, 197281, 4865609],
+    )]);
+}
+#[test]
+fn perft_kiwipete() {
+    perft_suite(&[(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2039, 97862, 4085603],
+    )]);
+}
+#[test]
+fn perft_positions() {
+    perft_suite(&[
+        (
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238, 674624],
+        ),
+        (
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467, 422333],
+        ),
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379, 2103487],
+        ),
+        (
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890, 3894594],
+        ),
+    ]);
+}
+#[test]
+fn perft_edge_cases() {
+    for (fen, depth, count) in [
+        // en passant
+        ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 6, 1134888),
+        ("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 6, 1015133),
+        ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 6, 1440467),
+        // castling
+        ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 6, 661072),
+        ("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 6, 803711),
+        ("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4, 1274206),
+        ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4, 1720476),
+        // promotion
+        ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 6, 3821001),
+        ("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 6, 217342),
+        ("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 6, 92683),
+        // stalemate, checkmate and double check
+        ("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 5, 1004658),
+        ("K1k5/8/P7/8/8/8/8/8 w - - 0 1", 6, 2217),
+        ("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 7, 567584),
+        ("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", 4, 23527),
+    ] {
+        let mut board = fen.parse::<Board>().ok().unwrap();
+        assert_eq!(board.perft(depth), count, "{}", fen);
+    }
+}
+#[test]
+fn perft_chess960() {
+    perft_suite(&[
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12189, 326672],
+        ),
+        (
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            &[21, 807, 18002, 667366],
+        ),
+        (
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            &[20, 479, 10471, 273318],
+        ),
+        (
+            "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+            &[28, 1120, 31058, 1171749],
+        ),
+        (
+            "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9",
+            &[29, 502, 14569, 287739],
+        ),
+        (
+            "rbbqn1kr/pp2p1pp/6n1/2pp1p2/2P4P/P7/BP1PPPP1/R1BQNNKR w HAha - 0 9",
+            &[27, 916, 25798, 890435],
+        ),
+        (
+            "rqbbknr1/1ppp2pp/p5n1/4pp2/P7/1PP5/1Q1PPPPP/R1BBKNRN w GAga - 0 9",
+            &[24, 600, 15347, 408207],
+        ),
+    ]);
+}
+// every move below `depth` survives packing, and its kind agrees with the board
+#[cfg(test)]
+fn check_encoding(board: &mut Board, depth: usize) -> u64 {
+    use crate::{moves::Move, PieceType};
+    if depth == 0 {
+        return 1;
+    }
+    let mut nodes = 0;
+    for piece_move in board.legal_moves() {
+        assert_eq!(Move::from_bits(piece_move.bits()), Some(piece_move));
+        assert!(!piece_move.is_null());
+        let (from, to) = (piece_move.from(), piece_move.to());
+        let piece = board.piece_on(from).unwrap();
+        let ours = board.colors(board.side_to_move).0;
+        let castling = piece == PieceType::King
+            && (board.pieces.rook.0 & ours & 1u64 << to > 0 || from.abs_diff(to) == 2);
+        let en_passant = piece == PieceType::Pawn && Some(to) == board.state.en_pessant;
+        let double_push = piece == PieceType::Pawn && from.abs_diff(to) == 16;
+        assert_eq!(piece_move.is_castling(), castling, "{}", board);
+        assert_eq!(piece_move.is_en_passant(), en_passant, "{}", board);
+        assert_eq!(piece_move.is_double_push(), double_push, "{}", board);
+        if !(castling || en_passant || double_push) {
+            assert_eq!(
+                Move::new(from, to, piece_move.promotion()),
+                piece_move,
+                "{}",
+                board
+            );
+        }
+        let undo = board.make_move(piece_move);
+        nodes += check_encoding(board, depth - 1);
+        board.unmake_move(piece_move, undo);
+    }
+    nodes
+}
+#[test]
+fn move_encoding() {
+    assert_eq!(std::mem::size_of::<crate::moves::Move>(), 2);
+    for (fen, depth) in [
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+        ),
+        ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 4),
+        ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 4),
+        (
+            "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9",
+            3,
+        ),
+    ] {
+        let mut board = fen.parse::<Board>().unwrap();
+        let nodes = board.perft(depth);
+        assert_eq!(check_encoding(&mut board, depth), nodes, "{}", fen);
+    }
+}
@@ -0,0 +1,101 @@
+use once_cell::sync::Lazy;
+
+use crate::bitboard::BitBoard;
+use crate::magic::{bishop_mask, rook_mask, subsets};
+use crate::sliding_pieces::{bishop_fill, rook_fill, SliderAttacks};
+
+pub struct PextTable {
+    rook: [(u64, usize); 64],
+    bishop: [(u64, usize); 64],
+    attacks: Vec<BitBoard>,
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+pub fn pext(value: u64, mask: u64) -> u64 {
+    unsafe { core::arch::x86_64::_pext_u64(value, mask) }
+}
+// same result one mask bit at a time, lets the table be tested on any machine
+#[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+pub fn pext(value: u64, mut mask: u64) -> u64 {
+    let mut result = 0;
+    let mut bit = 1;
+    while mask > 0 {
+        if value & mask & mask.wrapping_neg() > 0 {
+            result |= bit;
+        }
+        bit <<= 1;
+        mask &= mask - 1;
+    }
+    result
+}
+
+pub static PEXT: Lazy<PextTable> = Lazy::new(PextTable::new);
+
+pub struct PextAttacks;
+impl SliderAttacks for PextAttacks {
+    fn rook_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+        let (mask, offset) = PEXT.rook[square];
+        PEXT.attacks[offset + pext(occupied.0, mask) as usize]
+    }
+    fn bishop_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+        let (mask, offset) = PEXT.bishop[square];
+        PEXT.attacks[offset + pext(occupied.0, mask) as usize]
+    }
+}
+
+impl PextTable {
+    fn new() -> Self {
+        let mut attacks = vec![];
+        let rook = Self::fill_table(&mut attacks, rook_mask, rook_fill);
+        let bishop = Self::fill_table(&mut attacks, bishop_mask, bishop_fill);
+        PextTable {
+            rook,
+            bishop,
+            attacks,
+        }
+    }
+    fn fill_table(
+        attacks: &mut Vec<BitBoard>,
+        mask: fn(usize) -> u64,
+        fill: fn(u64, u64) -> u64,
+    ) -> [(u64, usize); 64] {
+        let mut table = [(0, 0); 64];
+        for (square, entry) in table.iter_mut().enumerate() {
+            let mask = mask(square);
+            *entry = (mask, attacks.len());
+            attacks.resize(attacks.len() + (1 << mask.count_ones()), BitBoard::EMPTY);
+            for occupied in subsets(mask) {
+                attacks[entry.1 + pext(occupied, mask) as usize] =
+                    BitBoard(fill(1u64 << square, !occupied));
+            }
+        }
+        table
+    }
+}
+
+#[test]
+fn pext_matches_magic() {
+    use crate::magic::{MagicAttacks, Xorshift};
+    let mut rng = Xorshift(0x1234567);
+    for square in 0..64 {
+        for occupied in subsets(rook_mask(square)) {
+            assert_eq!(
+                PextAttacks::rook_attacks(square, BitBoard(occupied)),
+                MagicAttacks::rook_attacks(square, BitBoard(occupied))
+            );
+        }
+        for occupied in subsets(bishop_mask(square)) {
+            assert_eq!(
+                PextAttacks::bishop_attacks(square, BitBoard(occupied)),
+                MagicAttacks::bishop_attacks(square, BitBoard(occupied))
+            );
+        }
+        for _ in 0..10000 {
+            let occupied = BitBoard(rng.next_u64() & rng.next_u64());
+            assert_eq!(
+                PextAttacks::queen_attacks(square, occupied),
+                MagicAttacks::queen_attacks(square, occupied)
+            );
+        }
+    }
+}
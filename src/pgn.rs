@@ -0,0 +1,767 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::color::Color;
+use crate::fen::FenError;
+use crate::notation::MoveParseError;
+use crate::{board::Board, moves::Move};
+
+/// A move in a game tree with what was written around it. Each variation is
+/// an alternative to this move, played from the position before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub piece_move: Move,
+    pub nags: Vec<u8>,
+    // a comment in front of the move, usually one opening a variation
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    pub variations: Vec<Vec<Node>>,
+}
+impl Node {
+    pub fn new(piece_move: Move) -> Self {
+        Node {
+            piece_move,
+            nags: vec![],
+            comment_before: None,
+            comment: None,
+            variations: vec![],
+        }
+    }
+    /// The clock time left after the move, from a `[%clk 0:03:25]` command in its comment.
+    pub fn clock(&self) -> Option<Duration> {
+        let comment = self.comment.as_deref()?;
+        let start = comment.find("[%clk ")? + "[%clk ".len();
+        let end = start + comment[start..].find(']')?;
+        // h:mm:ss with fractional seconds, hours and minutes may be left out
+        let mut parts = comment[start..end].trim().rsplit(':');
+        let seconds = parts.next()?.parse::<f64>().ok()?;
+        if !seconds.is_finite() || seconds < 0.0 {
+            return None;
+        }
+        let mut whole = 0u64;
+        for (index, part) in parts.enumerate() {
+            if index > 1 || !part.bytes().all(|byte| byte.is_ascii_digit()) {
+                return None;
+            }
+            let unit = if index == 0 { 60 } else { 3600 };
+            whole = whole.checked_add(part.parse::<u64>().ok()?.checked_mul(unit)?)?;
+        }
+        Duration::from_secs(whole).checked_add(Duration::try_from_secs_f64(seconds).ok()?)
+    }
+    // replaces the comment's clock command, or starts the comment with one
+    pub fn set_clock(&mut self, clock: Duration) {
+        let seconds = clock.as_secs();
+        let command = format!(
+            "[%clk {}:{:02}:{:02}]",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        let comment = self.comment.get_or_insert_with(String::new);
+        let old = comment.find("[%clk ").and_then(|start| {
+            let end = start + comment[start..].find(']')?;
+            Some(start..end + 1)
+        });
+        match old {
+            Some(range) => comment.replace_range(range, &command),
+            None if comment.is_empty() => *comment = command,
+            None => *comment = format!("{} {}", command, comment),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    // the FEN tag's position, or the standard one
+    pub start: Board,
+    pub moves: Vec<Node>,
+    // the termination marker, "1-0", "0-1", "1/2-1/2" or "*"
+    pub result: String,
+}
+impl Game {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+const LINE_WIDTH: usize = 80;
+
+// movetext tokens for `line` played from `board`, which is left as it was found
+fn movetext(board: &mut Board, line: &[Node], tokens: &mut Vec<String>) {
+    let mut undos = vec![];
+    // black's moves need their number after anything that breaks up the moves
+    let mut interrupted = true;
+    for node in line {
+        if let Some(comment) = &node.comment_before {
+            push_comment(comment, tokens);
+            interrupted = true;
+        }
+        match board.side_to_move {
+            Color::White => tokens.push(format!("{}.", board.fullmoves)),
+            Color::Black if interrupted => tokens.push(format!("{}...", board.fullmoves)),
+            Color::Black => {}
+        }
+        tokens.push(board.to_san(node.piece_move));
+        interrupted = false;
+        for nag in &node.nags {
+            tokens.push(format!("${}", nag));
+        }
+        if let Some(comment) = &node.comment {
+            push_comment(comment, tokens);
+            interrupted = true;
+        }
+        for variation in &node.variations {
+            let first = tokens.len();
+            movetext(board, variation, tokens);
+            match tokens.get_mut(first) {
+                Some(token) => token.insert(0, '('),
+                None => tokens.push("(".to_owned()),
+            }
+            tokens.last_mut().unwrap().push(')');
+            interrupted = true;
+        }
+        undos.push((node.piece_move, board.make_move(node.piece_move)));
+    }
+    while let Some((piece_move, undo)) = undos.pop() {
+        board.unmake_move(piece_move, undo);
+    }
+}
+// a word at a time so long comments wrap with the moves
+fn push_comment(comment: &str, tokens: &mut Vec<String>) {
+    let mut words: Vec<String> = comment.split_whitespace().map(str::to_owned).collect();
+    if words.is_empty() {
+        words.push(String::new());
+    }
+    words[0].insert(0, '{');
+    words.last_mut().unwrap().push('}');
+    tokens.extend(words);
+}
+fn write_tag(f: &mut fmt::Formatter<'_>, name: &str, value: &str) -> fmt::Result {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    writeln!(f, "[{} \"{}\"]", name, value)
+}
+
+// the Seven Tag Roster in its order first, then the rest as they came
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => &self.result,
+                _ => self.tag(name).unwrap_or(default),
+            };
+            write_tag(f, name, value)?;
+        }
+        let custom_start = self.start.to_fen() != Board::default().to_fen();
+        for (name, value) in &self.tags {
+            let roster = SEVEN_TAG_ROSTER.iter().any(|(tag, _)| tag == name);
+            if !roster && !(custom_start && (name == "SetUp" || name == "FEN")) {
+                write_tag(f, name, value)?;
+            }
+        }
+        if custom_start {
+            let fen = match self.start.chess960 {
+                true => self.start.to_shredder_fen(),
+                false => self.start.to_fen(),
+            };
+            write_tag(f, "SetUp", "1")?;
+            write_tag(f, "FEN", &fen)?;
+        }
+        writeln!(f)?;
+        let mut tokens = vec![];
+        movetext(&mut self.start.clone(), &self.moves, &mut tokens);
+        tokens.push(self.result.clone());
+        let mut width = 0;
+        for token in tokens {
+            if width > 0 && width + 1 + token.len() > LINE_WIDTH {
+                writeln!(f)?;
+                width = 0;
+            }
+            if width > 0 {
+                write!(f, " ")?;
+                width += 1;
+            }
+            write!(f, "{}", token)?;
+            width += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+#[derive(Debug)]
+pub enum PgnErrorKind {
+    Io(io::Error),
+    Syntax(String),
+    Fen(FenError),
+    Move(MoveParseError),
+}
+
+// lines and columns count from 1 like an editor's
+#[derive(Debug)]
+pub struct PgnError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PgnErrorKind,
+}
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            PgnErrorKind::Io(error) => write!(f, "{}", error),
+            PgnErrorKind::Syntax(message) => write!(f, "{}", message),
+            PgnErrorKind::Fen(error) => write!(f, "bad FEN tag, {}", error),
+            PgnErrorKind::Move(error) => write!(f, "{}", error),
+        }
+    }
+}
+impl std::error::Error for PgnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            PgnErrorKind::Io(error) => Some(error),
+            PgnErrorKind::Syntax(_) => None,
+            PgnErrorKind::Fen(error) => Some(error),
+            PgnErrorKind::Move(error) => Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    San(String),
+    Open,
+    Close,
+    Result(String),
+    Eof,
+}
+
+// splits the input into tokens a line at a time, move numbers are dropped on the way
+struct Lexer<R> {
+    reader: R,
+    buf: String,
+    line: Vec<char>,
+    pos: usize,
+    line_number: usize,
+    peeked: Option<(Token, usize, usize)>,
+}
+impl<R: BufRead> Lexer<R> {
+    fn error(&self, line: usize, column: usize, message: &str) -> PgnError {
+        PgnError {
+            line,
+            column,
+            kind: PgnErrorKind::Syntax(message.to_owned()),
+        }
+    }
+    // the next character, reading on into the following lines as needed
+    fn peek_char(&mut self) -> Result<Option<char>, PgnError> {
+        while self.pos >= self.line.len() {
+            self.buf.clear();
+            let read = self
+                .reader
+                .read_line(&mut self.buf)
+                .map_err(|error| PgnError {
+                    line: self.line_number + 1,
+                    column: 1,
+                    kind: PgnErrorKind::Io(error),
+                })?;
+            if read == 0 {
+                return Ok(None);
+            }
+            self.line = self.buf.chars().collect();
+            self.pos = 0;
+            self.line_number += 1;
+        }
+        Ok(Some(self.line[self.pos]))
+    }
+    fn next_char(&mut self) -> Result<Option<char>, PgnError> {
+        let c = self.peek_char()?;
+        self.pos += 1;
+        Ok(c)
+    }
+    fn skip_whitespace(&mut self) -> Result<(), PgnError> {
+        while self.peek_char()?.is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        Ok(())
+    }
+    fn peek(&mut self) -> Result<&Token, PgnError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read_token()?);
+        }
+        Ok(&self.peeked.as_ref().unwrap().0)
+    }
+    // the token with the line and column it starts at
+    fn next(&mut self) -> Result<(Token, usize, usize), PgnError> {
+        match self.peeked.take() {
+            Some(peeked) => Ok(peeked),
+            None => self.read_token(),
+        }
+    }
+    fn read_token(&mut self) -> Result<(Token, usize, usize), PgnError> {
+        loop {
+            let Some(c) = self.peek_char()? else {
+                return Ok((Token::Eof, self.line_number, self.pos + 1));
+            };
+            let (line, column) = (self.line_number, self.pos + 1);
+            self.pos += 1;
+            let token = match c {
+                // an escaped line, left for other programs
+                '%' if column == 1 => {
+                    self.pos = self.line.len();
+                    continue;
+                }
+                c if c.is_whitespace() || c == '.' => continue,
+                ';' => {
+                    let text: String = self.line[self.pos..].iter().collect();
+                    self.pos = self.line.len();
+                    Token::Comment(text.trim().to_owned())
+                }
+                '{' => {
+                    let mut text = String::new();
+                    loop {
+                        match self.next_char()? {
+                            Some('}') => break,
+                            Some(c) => text.push(c),
+                            None => return Err(self.error(line, column, "unterminated comment")),
+                        }
+                    }
+                    // comments get rewrapped on output, the line breaks carry no meaning
+                    Token::Comment(text.split_whitespace().collect::<Vec<_>>().join(" "))
+                }
+                '(' => Token::Open,
+                ')' => Token::Close,
+                '*' => Token::Result("*".to_owned()),
+                '[' => self.read_tag(line, column)?,
+                '$' => {
+                    let mut digits = String::new();
+                    while let Some(c) = self.peek_char()?.filter(char::is_ascii_digit) {
+                        digits.push(c);
+                        self.pos += 1;
+                    }
+                    match digits.parse() {
+                        Ok(nag) => Token::Nag(nag),
+                        Err(_) => return Err(self.error(line, column, "bad annotation glyph")),
+                    }
+                }
+                '!' | '?' => {
+                    let mut suffix = c.to_string();
+                    while let Some(c) = self.peek_char()?.filter(|&c| c == '!' || c == '?') {
+                        suffix.push(c);
+                        self.pos += 1;
+                    }
+                    Token::Nag(match suffix.as_str() {
+                        "!" => 1,
+                        "?" => 2,
+                        "!!" => 3,
+                        "??" => 4,
+                        "!?" => 5,
+                        "?!" => 6,
+                        _ => return Err(self.error(line, column, "unknown move annotation")),
+                    })
+                }
+                c if c.is_ascii_alphanumeric() => {
+                    let mut symbol = c.to_string();
+                    while let Some(c) = self
+                        .peek_char()?
+                        .filter(|&c| c.is_ascii_alphanumeric() || "-=+#/:_".contains(c))
+                    {
+                        symbol.push(c);
+                        self.pos += 1;
+                    }
+                    match symbol.as_str() {
+                        "1-0" | "0-1" | "1/2-1/2" => Token::Result(symbol),
+                        _ if symbol.bytes().all(|b| b.is_ascii_digit()) => continue,
+                        _ => Token::San(symbol),
+                    }
+                }
+                _ => return Err(self.error(line, column, &format!("unexpected '{}'", c))),
+            };
+            return Ok((token, line, column));
+        }
+    }
+    // [Name "value"], the opening bracket already read
+    fn read_tag(&mut self, line: usize, column: usize) -> Result<Token, PgnError> {
+        let bad_tag = |lexer: &Self| lexer.error(line, column, "malformed tag pair");
+        self.skip_whitespace()?;
+        let mut name = String::new();
+        while let Some(c) = self
+            .peek_char()?
+            .filter(|&c| c.is_alphanumeric() || c == '_')
+        {
+            name.push(c);
+            self.pos += 1;
+        }
+        self.skip_whitespace()?;
+        if name.is_empty() || self.next_char()? != Some('"') {
+            return Err(bad_tag(self));
+        }
+        let mut value = String::new();
+        loop {
+            match self.next_char()? {
+                Some('"') => break,
+                Some('\\') => match self.next_char()? {
+                    Some(c @ ('"' | '\\')) => value.push(c),
+                    _ => return Err(bad_tag(self)),
+                },
+                Some('\n') | None => return Err(bad_tag(self)),
+                Some(c) => value.push(c),
+            }
+        }
+        self.skip_whitespace()?;
+        if self.next_char()? != Some(']') {
+            return Err(bad_tag(self));
+        }
+        Ok(Token::Tag(name, value))
+    }
+    // after an error, on to the next line opening a tag section
+    fn skip_game(&mut self) -> Result<(), PgnError> {
+        self.peeked = None;
+        let mut in_tags = self.line.first() == Some(&'[');
+        loop {
+            self.pos = self.line.len();
+            if self.peek_char()?.is_none() {
+                return Ok(());
+            }
+            let tag_line = self.line.first() == Some(&'[');
+            if tag_line && !in_tags {
+                return Ok(());
+            }
+            in_tags = tag_line;
+        }
+    }
+}
+
+/// Reads games one at a time from PGN text, never holding more than a line
+/// and the current game in memory.
+pub struct PgnReader<R = BufReader<File>> {
+    lexer: Lexer<R>,
+    failed: bool,
+}
+impl PgnReader {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(PgnReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        PgnReader {
+            lexer: Lexer {
+                reader,
+                buf: String::new(),
+                line: vec![],
+                pos: 0,
+                line_number: 0,
+                peeked: None,
+            },
+            failed: false,
+        }
+    }
+    /// The next game, or None at the end of the input. After an error the
+    /// rest of the broken game is skipped.
+    pub fn read_game(&mut self) -> Result<Option<Game>, PgnError> {
+        if self.failed {
+            self.failed = false;
+            self.lexer.skip_game()?;
+        }
+        let game = self.parse_game();
+        self.failed = game.is_err();
+        game
+    }
+    fn parse_game(&mut self) -> Result<Option<Game>, PgnError> {
+        let mut tags = vec![];
+        let mut fen_at = (0, 0);
+        while let Token::Tag(..) = self.lexer.peek()? {
+            let (Token::Tag(name, value), line, column) = self.lexer.next()? else {
+                unreachable!()
+            };
+            if name == "FEN" {
+                fen_at = (line, column);
+            }
+            tags.push((name, value));
+        }
+        if tags.is_empty() && *self.lexer.peek()? == Token::Eof {
+            return Ok(None);
+        }
+        let start = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => fen.parse::<Board>().map_err(|error| PgnError {
+                line: fen_at.0,
+                column: fen_at.1,
+                kind: PgnErrorKind::Fen(error),
+            })?,
+            None => Board::default(),
+        };
+        let (moves, result) = self.parse_line(start.clone(), false)?;
+        Ok(Some(Game {
+            tags,
+            start,
+            moves,
+            result: result.unwrap_or_else(|| "*".to_owned()),
+        }))
+    }
+    // moves up to the end of a variation, or of the game along with its result
+    fn parse_line(
+        &mut self,
+        mut board: Board,
+        nested: bool,
+    ) -> Result<(Vec<Node>, Option<String>), PgnError> {
+        let mut moves: Vec<Node> = vec![];
+        let mut last = None;
+        let mut comment_before: Option<String> = None;
+        loop {
+            let (token, line, column) = self.lexer.next()?;
+            let error = |kind| PgnError { line, column, kind };
+            let syntax = |message: &str| error(PgnErrorKind::Syntax(message.to_owned()));
+            match token {
+                Token::San(text) => {
+                    let piece_move = board
+                        .parse_san(&text)
+                        .map_err(|parse_error| error(PgnErrorKind::Move(parse_error)))?;
+                    last = Some((piece_move, board.make_move(piece_move)));
+                    moves.push(Node {
+                        comment_before: comment_before.take(),
+                        ..Node::new(piece_move)
+                    });
+                }
+                Token::Nag(nag) => match moves.last_mut() {
+                    Some(node) => node.nags.push(nag),
+                    None => return Err(syntax("annotation before any move")),
+                },
+                Token::Comment(text) => {
+                    let comment = match moves.last_mut() {
+                        Some(node) => &mut node.comment,
+                        None => &mut comment_before,
+                    };
+                    match comment {
+                        Some(comment) => {
+                            comment.push(' ');
+                            comment.push_str(&text);
+                        }
+                        None => *comment = Some(text),
+                    }
+                }
+                Token::Open => {
+                    let Some((piece_move, undo)) = last else {
+                        return Err(syntax("variation before any move"));
+                    };
+                    let mut before = board.clone();
+                    before.unmake_move(piece_move, undo);
+                    let (variation, _) = self.parse_line(before, true)?;
+                    moves.last_mut().unwrap().variations.push(variation);
+                }
+                Token::Close if nested => return Ok((moves, None)),
+                Token::Result(result) if !nested => return Ok((moves, Some(result))),
+                // the next game started without this one being terminated
+                Token::Tag(..) | Token::Eof if !nested => {
+                    self.lexer.peeked = Some((token, line, column));
+                    return Ok((moves, None));
+                }
+                Token::Eof => return Err(syntax("unterminated variation")),
+                Token::Close => return Err(syntax("unexpected ')'")),
+                Token::Result(_) => return Err(syntax("game result inside a variation")),
+                Token::Tag(..) => return Err(syntax("tag pair inside a variation")),
+            }
+        }
+    }
+}
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_game().transpose()
+    }
+}
+
+#[cfg(test)]
+const SAMPLE_PGN: &str = r#"[Event "Casual \"blitz\""]
+[Site "?"]
+[Date "2024.01.01"]
+[Round "-"]
+[White "Anderssen, A."]
+[Black "Kieseritzky, L."]
+[Result "1-0"]
+
+{An old favourite} 1. e4 e5 2. f4!? exf4 $6 3. Bc4 (3. Nf3 {the usual}
+g5 (3... d6) 4. h4) 3... Qh4+ 4. Kf1 b5?! ; the Bryan countergambit
+% an escaped line
+5. Bxb5 Nf6 6. Nf3 Qh6 1-0
+
+[Event "Study"]
+[SetUp "1"]
+[FEN "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1"]
+
+1. b8=Q+ Kd7 *
+"#;
+#[cfg(test)]
+fn read_all(pgn: &str) -> Vec<Result<Game, PgnError>> {
+    PgnReader::new(io::Cursor::new(pgn)).collect()
+}
+#[test]
+fn pgn_games() {
+    let games: Vec<Game> = read_all(SAMPLE_PGN)
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(games.len(), 2);
+    let game = &games[0];
+    assert_eq!(game.tag("Event"), Some("Casual \"blitz\""));
+    assert_eq!(game.tag("Black"), Some("Kieseritzky, L."));
+    assert_eq!(game.tag("Opening"), None);
+    assert_eq!(game.result, "1-0");
+    let mut board = Board::default();
+    let mainline: Vec<String> = game
+        .moves
+        .iter()
+        .map(|node| {
+            let san = board.to_san(node.piece_move);
+            board.make_move(node.piece_move);
+            san
+        })
+        .collect();
+    assert_eq!(
+        mainline.join(" "),
+        "e4 e5 f4 exf4 Bc4 Qh4+ Kf1 b5 Bxb5 Nf6 Nf3 Qh6"
+    );
+    let moves = &game.moves;
+    assert_eq!(moves[0].comment_before.as_deref(), Some("An old favourite"));
+    assert_eq!(
+        (moves[2].nags.as_slice(), moves[3].nags.as_slice()),
+        (&[5][..], &[6][..])
+    );
+    assert_eq!(moves[7].nags, [6]);
+    assert_eq!(moves[7].comment.as_deref(), Some("the Bryan countergambit"));
+    // 3. Nf3 replaces 3. Bc4, and 3... d6 replaces 3... g5 inside it
+    assert_eq!(moves[4].variations.len(), 1);
+    let variation = &moves[4].variations[0];
+    assert_eq!(variation.len(), 3);
+    assert_eq!(variation[0].comment.as_deref(), Some("the usual"));
+    assert_eq!(variation[1].variations.len(), 1);
+    assert_eq!(variation[1].variations[0].len(), 1);
+
+    let study = &games[1];
+    assert_eq!(
+        study.start,
+        "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1".parse::<Board>().unwrap()
+    );
+    assert_eq!(study.moves.len(), 2);
+    assert_eq!(study.result, "*");
+}
+#[test]
+fn pgn_errors() {
+    // an illegal move is reported where it stands and the next game still reads
+    let pgn = "[Event \"a\"]\n\n1. e4 e5\n2. Ke3 Nc6 1-0\n\n[Event \"b\"]\n\n1. d4 *\n";
+    let games = read_all(pgn);
+    assert_eq!(games.len(), 2);
+    let error = games[0].as_ref().unwrap_err();
+    assert_eq!((error.line, error.column), (4, 4));
+    assert!(matches!(
+        error.kind,
+        PgnErrorKind::Move(MoveParseError::IllegalMove(_))
+    ));
+    assert_eq!(games[1].as_ref().unwrap().tag("Event"), Some("b"));
+    for (pgn, line, column) in [
+        ("1. e4 (1. d4 d5 *", 1, 17),
+        ("1. e4 (1. d4 d5", 1, 16),
+        ("1. e4 {never closed\n\n", 1, 7),
+        ("1. e4 e5 ) *", 1, 10),
+        ("[Event \"a]\n1. e4 *", 1, 1),
+        ("[FEN \"8/8/8 w - - 0 1\"]\n1. e4 *", 1, 1),
+        ("1. e4 e5 2. Nf9 *", 1, 13),
+        ("1. e4 & *", 1, 7),
+    ] {
+        let games = read_all(pgn);
+        let error = games[0].as_ref().unwrap_err();
+        assert_eq!(
+            (error.line, error.column),
+            (line, column),
+            "{} {}",
+            pgn,
+            error
+        );
+    }
+}
+#[test]
+fn pgn_writer() {
+    let game = read_all("[White \"Morphy\"]\n[Annotator \"me\"]\n\n1. e4 e5 2. Nf3 $1 {develops} (2. f4 exf4) 2... d6 1-0")
+        .remove(0)
+        .unwrap();
+    assert_eq!(
+        game.to_string(),
+        "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"Morphy\"]\n\
+         [Black \"?\"]\n[Result \"1-0\"]\n[Annotator \"me\"]\n\n\
+         1. e4 e5 2. Nf3 $1 {develops} (2. f4 exf4) 2... d6 1-0\n"
+    );
+    // a set up position gets its tags, with black to move first
+    let mut study = Game {
+        tags: vec![],
+        start: "4k3/8/8/8/8/8/1p6/4K3 b - - 0 40".parse::<Board>().unwrap(),
+        moves: vec![],
+        result: "*".to_owned(),
+    };
+    study
+        .moves
+        .push(Node::new(study.start.parse_san("b1=Q+").unwrap()));
+    let text = study.to_string();
+    assert!(text
+        .contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/1p6/4K3 b - - 0 40\"]\n\n40... b1=Q+ *\n"));
+}
+#[test]
+fn pgn_round_trip() {
+    let long_comment =
+        "a comment long enough that it has to be wrapped over more than one line of movetext";
+    let mut pgn = SAMPLE_PGN.replace("the usual", long_comment);
+    pgn.push_str("\n[Event \"960\"]\n[FEN \"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BN1KR w Hhf - 2 9\"]\n\n9. O-O Nd7 *\n");
+    for game in read_all(&pgn) {
+        let mut game = game.unwrap();
+        game.moves[0].set_clock(Duration::from_secs(3 * 60 + 25));
+        let text = game.to_string();
+        assert!(
+            text.lines().all(|line| line.len() <= LINE_WIDTH),
+            "{}",
+            text
+        );
+        let again = read_all(&text).remove(0).unwrap();
+        assert_eq!(
+            (&again.start, &again.moves, &again.result),
+            (&game.start, &game.moves, &game.result)
+        );
+        assert_eq!(again.to_string(), text);
+        assert_eq!(again.moves[0].clock(), Some(Duration::from_secs(205)));
+    }
+}
+#[test]
+fn clock_annotations() {
+    let mut node = Node::new(Move::new(12, 28, None));
+    assert_eq!(node.clock(), None);
+    node.set_clock(Duration::from_secs(3725));
+    assert_eq!(node.comment.as_deref(), Some("[%clk 1:02:05]"));
+    node.comment = Some("good move [%clk 0:00:09.5]".to_owned());
+    assert_eq!(node.clock(), Some(Duration::from_millis(9500)));
+    node.set_clock(Duration::from_secs(8));
+    assert_eq!(node.comment.as_deref(), Some("good move [%clk 0:00:08]"));
+    // malformed clocks are ignored rather than trusted
+    for comment in [
+        "[%clk -1:00:05]",
+        "[%clk nan]",
+        "[%clk 0:00:-5]",
+        "[%clk 1:2:3:4]",
+    ] {
+        node.comment = Some(comment.to_owned());
+        assert_eq!(node.clock(), None, "{}", comment);
+    }
+}
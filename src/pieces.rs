@@ -1,6 +1,8 @@
+use std::fmt;
 use std::str::FromStr;
 
 use crate::bitboard::*;
+use crate::fen::FenError;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Pieces {
     pub king: BitBoard,
@@ -13,10 +15,8 @@ pub struct Pieces {
     pub white: BitBoard,
     pub black: BitBoard,
 }
-#[derive(Debug, PartialEq, Eq)]
-pub struct ParseFenError;
 impl FromStr for Pieces {
-    type Err = ParseFenError;
+    type Err = FenError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut king = BitBoard::EMPTY;
         let mut queen = BitBoard::EMPTY;
@@ -28,69 +28,47 @@ impl FromStr for Pieces {
         let mut white = BitBoard::EMPTY;
         let mut black = BitBoard::EMPTY;
 
-        let parsed: Vec<&str> = s.split("/").collect();
-        let mut rank_count = 0;
-        let mut offset = 0;
-        for rank in parsed {
-            assert!(rank_count <= 7);
-            for sq in 0..rank.len() {
-                let square = rank.as_bytes()[sq] as char;
-                let index = (7 - rank_count) * 8 + sq + offset;
-                match square {
-                    'K' => {
-                        king |= BitBoard::new(index);
-                        white |= BitBoard::new(index);
-                    }
-                    'Q' => {
-                        queen |= BitBoard::new(index);
-                        white |= BitBoard::new(index);
-                    }
-                    'R' => {
-                        rook |= BitBoard::new(index);
-                        white |= BitBoard::new(index);
-                    }
-                    'B' => {
-                        bishop |= BitBoard::new(index);
-                        white |= BitBoard::new(index);
-                    }
-                    'N' => {
-                        knight |= BitBoard::new(index);
-                        white |= BitBoard::new(index);
-                    }
-                    'P' => {
-                        pawn |= BitBoard::new(index);
-                        white |= BitBoard::new(index);
-                    }
-                    'k' => {
-                        king |= BitBoard::new(index);
-                        black |= BitBoard::new(index);
-                    }
-                    'q' => {
-                        queen |= BitBoard::new(index);
-                        black |= BitBoard::new(index);
-                    }
-                    'r' => {
-                        rook |= BitBoard::new(index);
-                        black |= BitBoard::new(index);
-                    }
-                    'b' => {
-                        bishop |= BitBoard::new(index);
-                        black |= BitBoard::new(index);
-                    }
-                    'n' => {
-                        knight |= BitBoard::new(index);
-                        black |= BitBoard::new(index);
-                    }
-                    'p' => {
-                        pawn |= BitBoard::new(index);
-                        black |= BitBoard::new(index);
-                    }
-                    ' ' => break,
-                    int => offset += int as usize - 49,
+        let placement = s
+            .split_whitespace()
+            .next()
+            .ok_or(FenError::MissingField("piece placement"))?;
+        let parsed: Vec<&str> = placement.split('/').collect();
+        if parsed.len() != 8 {
+            return Err(FenError::BadRankCount(parsed.len()));
+        }
+        for (rank_count, rank) in parsed.into_iter().enumerate() {
+            let mut file = 0;
+            for square in rank.chars() {
+                if let Some(empty) = square.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                    file += empty as usize;
+                    continue;
                 }
+                if file >= 8 {
+                    return Err(FenError::BadRankLength(8 - rank_count));
+                }
+                let index = BitBoard::new((7 - rank_count) * 8 + file);
+                match square.to_ascii_lowercase() {
+                    'k' => king |= index,
+                    'q' => queen |= index,
+                    'r' => rook |= index,
+                    'b' => bishop |= index,
+                    'n' => knight |= index,
+                    'p' => pawn |= index,
+                    _ => return Err(FenError::UnknownPiece(square)),
+                }
+                if square.is_ascii_uppercase() {
+                    white |= index;
+                } else {
+                    black |= index;
+                }
+                file += 1;
+            }
+            if file != 8 {
+                return Err(FenError::BadRankLength(8 - rank_count));
             }
-            rank_count += 1;
-            offset = 0;
+        }
+        if (king & white).count() != 1 || (king & black).count() != 1 {
+            return Err(FenError::BadKings);
         }
         Ok(Pieces {
             king,
@@ -104,3 +82,43 @@ impl FromStr for Pieces {
         })
     }
 }
+impl fmt::Display for Pieces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                let square = BitBoard::new(rank * 8 + file);
+                let piece = [
+                    (self.king, 'k'),
+                    (self.queen, 'q'),
+                    (self.rook, 'r'),
+                    (self.bishop, 'b'),
+                    (self.knight, 'n'),
+                    (self.pawn, 'p'),
+                ]
+                .into_iter()
+                .find(|(pieces, _)| (*pieces & square).0 > 0);
+                match piece {
+                    Some((_, piece)) => {
+                        if empty > 0 {
+                            write!(f, "{}", empty)?;
+                            empty = 0;
+                        }
+                        match (self.white & square).0 > 0 {
+                            true => write!(f, "{}", piece.to_ascii_uppercase())?,
+                            false => write!(f, "{}", piece)?,
+                        }
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                write!(f, "{}", empty)?;
+            }
+            if rank > 0 {
+                write!(f, "/")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -5,5 +5,28 @@ pub struct PinMask {
     pub h: BitBoard,
     pub v: BitBoard,
     pub d1: BitBoard,
-    pub d2: BitBoard    
-}
\ No newline at end of file
+    pub d2: BitBoard,
+}
+impl PinMask {
+    pub const EMPTY: Self = PinMask {
+        h: BitBoard::EMPTY,
+        v: BitBoard::EMPTY,
+        d1: BitBoard::EMPTY,
+        d2: BitBoard::EMPTY,
+    };
+    // squares a piece on `index` may move to without exposing its king
+    pub const fn movemask(&self, index: usize) -> BitBoard {
+        let piece = 1u64 << index;
+        if self.h.0 & piece > 0 {
+            self.h
+        } else if self.v.0 & piece > 0 {
+            self.v
+        } else if self.d1.0 & piece > 0 {
+            self.d1
+        } else if self.d2.0 & piece > 0 {
+            self.d2
+        } else {
+            BitBoard::FULL
+        }
+    }
+}
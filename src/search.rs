@@ -0,0 +1,382 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::board::Board;
+use crate::eval::evaluate;
+use crate::moves::{GenMode, Move};
+use crate::pawns::PawnTable;
+use crate::tt::{Bound, TranspositionTable};
+
+pub const INFINITY: i32 = 32000;
+pub const MATE: i32 = 31000;
+pub const MAX_PLY: usize = 128;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub depth: Option<usize>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+}
+
+// reported after every finished iteration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Info {
+    pub depth: usize,
+    pub score: i32,
+    pub nodes: u64,
+    pub time: Duration,
+    pub pv: Vec<Move>,
+    // permille of the transposition table in use
+    pub hashfull: usize,
+}
+impl Info {
+    pub fn best_move(&self) -> Move {
+        self.pv[0]
+    }
+}
+
+/// Moves until mate for mate scores, negative when the side to move gets mated.
+pub const fn mate_in(score: i32) -> Option<i32> {
+    if score >= MATE - MAX_PLY as i32 {
+        Some((MATE - score + 1) / 2)
+    } else if score <= -MATE + MAX_PLY as i32 {
+        Some(-(MATE + score) / 2)
+    } else {
+        None
+    }
+}
+
+pub struct Search<'a> {
+    board: Board,
+    limits: Limits,
+    stop: &'a AtomicBool,
+    tt: &'a TranspositionTable,
+    pawns: PawnTable,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+    // triangular pv table, pv[ply] is the best line found from ply onwards
+    pv: Vec<Vec<Move>>,
+    // the previous iteration's pv, tried first while we are still on it
+    last_pv: Vec<Move>,
+    // moves made from the root to reach the current node
+    path: Vec<Move>,
+}
+impl<'a> Search<'a> {
+    pub fn new(
+        board: Board,
+        limits: Limits,
+        stop: &'a AtomicBool,
+        tt: &'a TranspositionTable,
+    ) -> Self {
+        Search {
+            board,
+            limits,
+            stop,
+            tt,
+            pawns: PawnTable::default(),
+            start: Instant::now(),
+            nodes: 0,
+            stopped: false,
+            pv: vec![vec![]; MAX_PLY + 1],
+            last_pv: vec![],
+            path: vec![],
+        }
+    }
+    // iterative deepening, the last finished iteration is the result
+    pub fn run(&mut self, mut report: impl FnMut(&Info)) -> Option<Info> {
+        let moves = self.board.legal_moves();
+        let mut result = Info {
+            depth: 0,
+            score: 0,
+            nodes: 0,
+            time: Duration::ZERO,
+            pv: vec![*moves.first()?],
+            hashfull: 0,
+        };
+        self.tt.new_search();
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY).clamp(1, MAX_PLY);
+        for depth in 1..=max_depth {
+            let score = self.negamax(depth, 0, -INFINITY, INFINITY);
+            if self.stopped {
+                break;
+            }
+            self.last_pv = self.pv[0].clone();
+            result = Info {
+                depth,
+                score,
+                nodes: self.nodes,
+                time: self.start.elapsed(),
+                pv: self.pv[0].clone(),
+                hashfull: self.tt.hashfull(),
+            };
+            report(&result);
+            // a forced mate won't change with more depth, and a lone reply
+            // isn't worth the clock time
+            let forced = moves.len() == 1 && self.limits.movetime.is_some();
+            if forced || mate_in(score).is_some() {
+                break;
+            }
+        }
+        Some(result)
+    }
+    // principal variation search, fail soft
+    fn negamax(&mut self, depth: usize, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        self.pv[ply].clear();
+        if self.nodes & 1023 == 0 && self.out_of_time() {
+            self.stopped = true;
+        }
+        if self.stopped {
+            return 0;
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(ply, alpha, beta);
+        }
+        // a single repetition is enough, whatever worked the first time works again
+        if ply > 0 && (self.board.repetitions() > 0 || self.board.insufficient_material()) {
+            return 0;
+        }
+        let hash = self.board.hash();
+        let entry = self.tt.probe(hash, ply);
+        // pv nodes search on so the line stays whole
+        let pv_node = beta - alpha > 1;
+        if let Some(entry) = entry.filter(|entry| ply > 0 && !pv_node && entry.depth >= depth) {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if cutoff {
+                return entry.score;
+            }
+        }
+        let mut moves = self.board.legal_moves();
+        if moves.is_empty() {
+            return match self.board.is_check() {
+                false => 0,
+                true => -MATE + ply as i32,
+            };
+        }
+        // checked after mate, a mate on the hundredth halfmove still counts
+        if ply > 0 && self.board.halfmoves >= 100 {
+            return 0;
+        }
+        self.order(&mut moves, ply, entry.and_then(|entry| entry.best_move));
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        for (i, &piece_move) in moves.iter().enumerate() {
+            let undo = self.board.make_move(piece_move);
+            self.path.push(piece_move);
+            let mut score;
+            if i == 0 {
+                score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            } else {
+                // prove the move is no better than the pv with a null window first
+                score = -self.negamax(depth - 1, ply + 1, -alpha - 1, -alpha);
+                if score > alpha && score < beta {
+                    score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+                }
+            }
+            self.path.pop();
+            self.board.unmake_move(piece_move, undo);
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = Some(piece_move);
+                    let (line, rest) = self.pv.split_at_mut(ply + 1);
+                    line[ply].clear();
+                    line[ply].push(piece_move);
+                    line[ply].extend_from_slice(&rest[0]);
+                }
+                if score >= beta {
+                    break;
+                }
+            }
+        }
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt.store(hash, depth, bound, best, best_move, ply);
+        best
+    }
+    // captures and promotions until the position is quiet, the side to move
+    // may stand pat on the static score unless it is in check
+    fn quiescence(&mut self, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        self.pv[ply].clear();
+        if self.nodes & 1023 == 0 && self.out_of_time() {
+            self.stopped = true;
+        }
+        if self.stopped {
+            return 0;
+        }
+        if ply >= MAX_PLY {
+            return evaluate(&self.board, &mut self.pawns);
+        }
+        let in_check = self.board.is_check();
+        let mut best = -INFINITY;
+        let mut moves;
+        if in_check {
+            moves = self.board.generate(GenMode::Evasions);
+            if moves.is_empty() {
+                return -MATE + ply as i32;
+            }
+        } else {
+            // stalemates go unnoticed here, only captures and promotions are looked at
+            best = evaluate(&self.board, &mut self.pawns);
+            if best >= beta {
+                return best;
+            }
+            alpha = alpha.max(best);
+            moves = self.board.generate(GenMode::Captures);
+            // exchanges that lose material can't lift us above standing pat
+            moves.retain(|&piece_move| self.board.see(piece_move) >= 0);
+        }
+        self.order(&mut moves, ply, None);
+        for piece_move in moves {
+            let undo = self.board.make_move(piece_move);
+            self.path.push(piece_move);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.path.pop();
+            self.board.unmake_move(piece_move, undo);
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    let (line, rest) = self.pv.split_at_mut(ply + 1);
+                    line[ply].clear();
+                    line[ply].push(piece_move);
+                    line[ply].extend_from_slice(&rest[0]);
+                }
+                if score >= beta {
+                    break;
+                }
+            }
+        }
+        best
+    }
+    // the last pv move first while we're still following it, then the table's move,
+    // winning and even captures by exchange value, quiet moves, and losing captures last
+    fn order(&self, moves: &mut [Move], ply: usize, tt_move: Option<Move>) {
+        let pv_move = match self.last_pv.starts_with(&self.path) {
+            true => self.last_pv.get(ply).copied(),
+            false => None,
+        };
+        moves.sort_by_cached_key(|&piece_move| {
+            if Some(piece_move) == pv_move {
+                return i32::MIN;
+            }
+            if Some(piece_move) == tt_move {
+                return i32::MIN + 1;
+            }
+            if self.board.captured(piece_move).is_none() && piece_move.promotion().is_none() {
+                return 0;
+            }
+            match self.board.see(piece_move) {
+                see if see >= 0 => -10000 - see,
+                see => -see,
+            }
+        });
+    }
+    fn out_of_time(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+            || self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || self
+                .limits
+                .movetime
+                .is_some_and(|movetime| self.start.elapsed() >= movetime)
+    }
+}
+
+#[cfg(test)]
+fn search_depth(fen: &str, depth: usize) -> Info {
+    let stop = AtomicBool::new(false);
+    let limits = Limits {
+        depth: Some(depth),
+        ..Limits::default()
+    };
+    let board = fen.parse::<Board>().unwrap();
+    let tt = TranspositionTable::new(1);
+    Search::new(board, limits, &stop, &tt).run(|_| {}).unwrap()
+}
+#[test]
+fn finds_mates() {
+    let info = search_depth("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+    assert_eq!(info.best_move(), Move::new(0, 56, None));
+    assert_eq!(mate_in(info.score), Some(1));
+    // Rh7 and Ra8, the rook ladder
+    let info = search_depth("1k6/8/8/8/8/8/R7/K6R w - - 0 1", 5);
+    assert_eq!(mate_in(info.score), Some(2), "{:?}", info);
+    // the losing side sees its own mate coming
+    let info = search_depth("7k/8/6K1/8/8/8/8/1Q6 b - - 0 1", 4);
+    assert_eq!(mate_in(info.score), Some(-1), "{:?}", info);
+}
+#[test]
+fn pv_is_legal() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let info = search_depth(fen, 4);
+    assert_eq!(info.depth, 4);
+    let mut board = fen.parse::<Board>().unwrap();
+    for piece_move in info.pv {
+        assert!(board.legal_moves().contains(&piece_move));
+        board.make_move(piece_move);
+    }
+}
+#[test]
+fn mate_scores() {
+    assert_eq!(mate_in(MATE - 1), Some(1));
+    assert_eq!(mate_in(MATE - 3), Some(2));
+    assert_eq!(mate_in(-MATE + 2), Some(-1));
+    assert_eq!(mate_in(-MATE), Some(0));
+    assert_eq!(mate_in(250), None);
+}
+#[test]
+fn quiescence_sees_recapture() {
+    // the pawn is defended, grabbing it loses the queen
+    let info = search_depth("4k3/8/3p4/4p3/8/8/8/4QK2 w - - 0 1", 1);
+    assert_ne!(info.best_move(), Move::new(4, 36, None), "{:?}", info);
+    assert!(info.score > -100, "{:?}", info);
+}
+#[test]
+fn transposition_table_is_reused() {
+    let stop = AtomicBool::new(false);
+    let limits = Limits {
+        depth: Some(5),
+        ..Limits::default()
+    };
+    let board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+        .parse::<Board>()
+        .unwrap();
+    let tt = TranspositionTable::new(1);
+    let first = Search::new(board.clone(), limits, &stop, &tt)
+        .run(|_| {})
+        .unwrap();
+    assert!(first.hashfull > 0);
+    let second = Search::new(board, limits, &stop, &tt).run(|_| {}).unwrap();
+    assert!(
+        second.nodes < first.nodes,
+        "{} {}",
+        second.nodes,
+        first.nodes
+    );
+    assert_eq!(second.score, first.score);
+}
+#[test]
+fn perpetual_check_is_a_draw() {
+    // two rooks down and about to be mated, white checks forever
+    let info = search_depth("5rk1/5p1p/8/8/8/4Q3/rr6/7K w - - 0 1", 8);
+    assert_eq!(info.score, 0, "{:?}", info);
+}
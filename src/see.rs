@@ -0,0 +1,145 @@
+use crate::moves::{between_rays, Move};
+use crate::{bitboard::BitBoard, board::Board, color::Color, PieceType};
+use crate::{BISHOP_LOOKUP, KING_LOOKUP, KNIGHT_LOOKUP, ROOK_LOOKUP};
+
+// cheapest first, the order exchanges are played in
+const ATTACKER_ORDER: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+impl Board {
+    // the piece a move takes, en passant included
+    pub fn captured(&self, piece_move: Move) -> Option<PieceType> {
+        let to = piece_move.to();
+        if self.colors(!self.side_to_move).0 & 1u64 << to > 0 {
+            return self.piece_on(to);
+        }
+        let pawn = self.pieces.pawn.0 & 1u64 << piece_move.from() > 0;
+        (pawn && Some(to) == self.state.en_pessant).then_some(PieceType::Pawn)
+    }
+    // every piece of either color attacking `index` through `occupied`,
+    // sliders behind a piece that has left `occupied` show up as x-rays
+    pub fn attackers_to(&self, index: usize, occupied: BitBoard) -> BitBoard {
+        let occupied = occupied.0;
+        let straight = (self.pieces.rook.0 | self.pieces.queen.0) & ROOK_LOOKUP[index].0;
+        let diagonal = (self.pieces.bishop.0 | self.pieces.queen.0) & BISHOP_LOOKUP[index].0;
+        let mut sliders = (straight | diagonal) & occupied;
+        let mut attackers = KNIGHT_LOOKUP[index].0 & self.pieces.knight.0
+            | KING_LOOKUP[index].0 & self.pieces.king.0
+            | self.pawn_attacks(index, Color::White).0 & self.pieces.pawn.0 & self.pieces.black.0
+            | self.pawn_attacks(index, Color::Black).0 & self.pieces.pawn.0 & self.pieces.white.0;
+        while sliders > 0 {
+            let slider = sliders.trailing_zeros() as usize;
+            if between_rays(BitBoard::new(slider), BitBoard::new(index)).0 & occupied == 0 {
+                attackers |= 1u64 << slider;
+            }
+            sliders &= sliders - 1;
+        }
+        BitBoard(attackers & occupied)
+    }
+    /// Static exchange evaluation: the material the side to move comes out with
+    /// after both sides keep recapturing on the target square with their cheapest piece.
+    pub fn see(&self, piece_move: Move) -> i32 {
+        let (from, to) = (piece_move.from(), piece_move.to());
+        let mover = match self.piece_on(from) {
+            Some(piece_type) => piece_type,
+            None => return 0,
+        };
+        // castling never wins or loses anything
+        if self.colors(self.side_to_move).0 & 1u64 << to > 0 {
+            return 0;
+        }
+        let mut occupied = self.occupied().0 ^ 1u64 << from;
+        let mut gain = [0i32; 32];
+        gain[0] = self.captured(piece_move).map_or(0, PieceType::value);
+        if mover == PieceType::Pawn && Some(to) == self.state.en_pessant {
+            occupied ^= 1u64 << if to > from { to - 8 } else { to + 8 };
+        }
+        let mut on_square = mover;
+        if let Some(promotion) = piece_move.promotion() {
+            gain[0] += promotion.value() - PieceType::Pawn.value();
+            on_square = promotion;
+        }
+        let mut side = !self.side_to_move;
+        let mut depth = 0;
+        loop {
+            let attackers = self.attackers_to(to, BitBoard(occupied)).0 & self.colors(side).0;
+            let Some((attacker, square)) = ATTACKER_ORDER.into_iter().find_map(|piece_type| {
+                let bb = attackers & self.pieces(piece_type).0;
+                (bb > 0).then(|| (piece_type, bb.trailing_zeros() as usize))
+            }) else {
+                break;
+            };
+            // the king can only take last
+            if attacker == PieceType::King
+                && self.attackers_to(to, BitBoard(occupied ^ 1u64 << square)).0
+                    & self.colors(!side).0
+                    > 0
+            {
+                break;
+            }
+            depth += 1;
+            gain[depth] = on_square.value() - gain[depth - 1];
+            occupied ^= 1u64 << square;
+            on_square = attacker;
+            side = !side;
+            if depth == gain.len() - 1 {
+                break;
+            }
+        }
+        // either side may stop recapturing once it stops paying
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
+        }
+        gain[0]
+    }
+}
+
+#[test]
+fn see_exchanges() {
+    use crate::fen::parse_square;
+    let see = |fen: &str, from: &str, to: &str| {
+        let board = fen.parse::<Board>().unwrap();
+        let piece_move = Move::new(parse_square(from).unwrap(), parse_square(to).unwrap(), None);
+        board.see(piece_move)
+    };
+    // undefended pawn
+    assert_eq!(
+        see(
+            "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+            "e1",
+            "e5"
+        ),
+        100
+    );
+    // both queens join in from behind the rook and the bishop
+    assert_eq!(
+        see(
+            "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+            "d3",
+            "e5"
+        ),
+        -220
+    );
+    // queen takes a defended pawn
+    assert_eq!(see("4k3/8/3p4/4p3/8/8/8/4QK2 w - - 0 1", "e1", "e5"), -800);
+    // rook backed up by a queen wins the exchange against rook and pawn
+    assert_eq!(see("4k3/4r3/8/4p3/8/8/4R3/4QK2 w - - 0 1", "e2", "e5"), 100);
+    // the king may only recapture when nothing takes it back
+    assert_eq!(see("6k1/8/8/3p4/4n3/3K4/8/4R3 w - - 0 1", "e1", "e4"), -80);
+    assert_eq!(
+        see("4r1k1/8/8/3p4/4n3/3K4/8/4R3 w - - 0 1", "e1", "e4"),
+        -180
+    );
+    // quiet moves onto attacked and safe squares
+    assert_eq!(see("4k3/8/8/8/3p4/8/8/2B1K3 w - - 0 1", "c1", "e3"), -330);
+    assert_eq!(see("4k3/8/8/8/3p4/8/8/2B1K3 w - - 0 1", "c1", "d2"), 0);
+    // en passant
+    assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5", "d6"), 100);
+}
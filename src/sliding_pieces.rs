@@ -1,3 +1,5 @@
+use crate::bitboard::BitBoard;
+
 pub fn sout_attacks(mut pieces: u64, empty: u64) -> u64 {
     for _ in 0..7 {
         pieces |= (pieces >> 8) & empty;
@@ -58,3 +60,38 @@ pub fn sowe_attacks(mut pieces: u64, mut empty: u64) -> u64 {
     }
     (pieces >> 9) & NOT_H
 }
+pub fn rook_fill(pieces: u64, empty: u64) -> u64 {
+    nort_attacks(pieces, empty)
+        | sout_attacks(pieces, empty)
+        | east_attacks(pieces, empty)
+        | west_attacks(pieces, empty)
+}
+pub fn bishop_fill(pieces: u64, empty: u64) -> u64 {
+    noea_attacks(pieces, empty)
+        | nowe_attacks(pieces, empty)
+        | soea_attacks(pieces, empty)
+        | sowe_attacks(pieces, empty)
+}
+
+pub trait SliderAttacks {
+    fn rook_attacks(square: usize, occupied: BitBoard) -> BitBoard;
+    fn bishop_attacks(square: usize, occupied: BitBoard) -> BitBoard;
+    fn queen_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+        Self::rook_attacks(square, occupied) | Self::bishop_attacks(square, occupied)
+    }
+}
+// pext is only worth it with a hardware instruction, otherwise stay on magics
+#[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+pub type Sliders = crate::pext::PextAttacks;
+#[cfg(not(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2")))]
+pub type Sliders = crate::magic::MagicAttacks;
+
+pub fn rook_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+    Sliders::rook_attacks(square, occupied)
+}
+pub fn bishop_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+    Sliders::bishop_attacks(square, occupied)
+}
+pub fn queen_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+    Sliders::queen_attacks(square, occupied)
+}
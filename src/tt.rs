@@ -0,0 +1,215 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::moves::Move;
+use crate::search::{MATE, MAX_PLY};
+
+pub const DEFAULT_MB: usize = 16;
+// the range the Hash option advertises
+pub const MIN_MB: usize = 1;
+pub const MAX_MB: usize = 65536;
+const BUCKET_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact = 1,
+    Lower = 2,
+    Upper = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtEntry {
+    pub depth: usize,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<Move>,
+}
+
+// the key is stored xored with the data, a torn write from another thread
+// then fails the key check instead of handing back another position's data
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+#[derive(Default)]
+#[repr(align(64))]
+struct Bucket {
+    slots: [Slot; BUCKET_SIZE],
+}
+
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: AtomicU8,
+}
+impl TranspositionTable {
+    pub fn new(mb: usize) -> Self {
+        let buckets = (mb.clamp(MIN_MB, MAX_MB) << 20) / std::mem::size_of::<Bucket>();
+        TranspositionTable {
+            buckets: (0..buckets).map(|_| Bucket::default()).collect(),
+            age: AtomicU8::new(0),
+        }
+    }
+    pub fn clear(&self) {
+        for slot in self.buckets.iter().flat_map(|bucket| &bucket.slots) {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+    // entries from earlier searches are replaced first
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+    fn bucket(&self, hash: u64) -> &Bucket {
+        &self.buckets[((hash as u128 * self.buckets.len() as u128) >> 64) as usize]
+    }
+    pub fn probe(&self, hash: u64, ply: usize) -> Option<TtEntry> {
+        self.bucket(hash).slots.iter().find_map(|slot| {
+            let data = slot.data.load(Ordering::Relaxed);
+            let entry = unpack(data)?;
+            (slot.key.load(Ordering::Relaxed) ^ data == hash).then_some(TtEntry {
+                score: score_from_tt(entry.score, ply),
+                ..entry
+            })
+        })
+    }
+    pub fn store(
+        &self,
+        hash: u64,
+        depth: usize,
+        bound: Bound,
+        score: i32,
+        best_move: Option<Move>,
+        ply: usize,
+    ) {
+        let age = self.age.load(Ordering::Relaxed) & 63;
+        let slots = &self.bucket(hash).slots;
+        let matching = slots.iter().find(|slot| {
+            let data = slot.data.load(Ordering::Relaxed);
+            slot.key.load(Ordering::Relaxed) ^ data == hash
+        });
+        // otherwise the shallowest entry, counting each search of age as 4 plies
+        let slot = matching.unwrap_or_else(|| {
+            slots
+                .iter()
+                .min_by_key(|slot| {
+                    let data = slot.data.load(Ordering::Relaxed);
+                    let stale = (age.wrapping_sub((data >> 42) as u8) & 63) as usize;
+                    match unpack(data) {
+                        Some(entry) => entry.depth as isize - 4 * stale as isize,
+                        None => isize::MIN,
+                    }
+                })
+                .unwrap()
+        });
+        let mut best_move = best_move;
+        if let (None, Some(old)) = (
+            best_move,
+            matching.and_then(|s| unpack(s.data.load(Ordering::Relaxed))),
+        ) {
+            // an upper bound has no move of its own, keep the one we had
+            best_move = old.best_move;
+        }
+        let data = best_move.map_or(0, |piece_move| piece_move.bits() as u64)
+            | (score_to_tt(score, ply) as i16 as u16 as u64) << 16
+            | (depth.min(255) as u64) << 32
+            | (bound as u64) << 40
+            | (age as u64) << 42;
+        slot.key.store(hash ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+    /// Permille of a sample of the table filled during the current search, for UCI's hashfull.
+    pub fn hashfull(&self) -> usize {
+        let age = (self.age.load(Ordering::Relaxed) & 63) as u64;
+        let sample = self.buckets.iter().take(1000 / BUCKET_SIZE);
+        let mut filled = 0;
+        let mut total = 0;
+        for slot in sample.flat_map(|bucket| &bucket.slots) {
+            let data = slot.data.load(Ordering::Relaxed);
+            if unpack(data).is_some() && data >> 42 & 63 == age {
+                filled += 1;
+            }
+            total += 1;
+        }
+        filled * 1000 / total.max(1)
+    }
+}
+
+// mate scores are stored relative to the entry's position rather than the root
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    match score {
+        s if s >= MATE - MAX_PLY as i32 => s + ply as i32,
+        s if s <= -MATE + MAX_PLY as i32 => s - ply as i32,
+        s => s,
+    }
+}
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    match score {
+        s if s >= MATE - MAX_PLY as i32 => s - ply as i32,
+        s if s <= -MATE + MAX_PLY as i32 => s + ply as i32,
+        s => s,
+    }
+}
+
+fn unpack(data: u64) -> Option<TtEntry> {
+    let bound = match data >> 40 & 3 {
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        3 => Bound::Upper,
+        _ => return None,
+    };
+    let best_move = Move::from_bits(data as u16).filter(|piece_move| !piece_move.is_null());
+    Some(TtEntry {
+        depth: (data >> 32 & 255) as usize,
+        bound,
+        score: (data >> 16) as u16 as i16 as i32,
+        best_move,
+    })
+}
+
+#[test]
+fn tt_store_probe() {
+    let tt = TranspositionTable::new(1);
+    let piece_move = Move::new(52, 60, Some(crate::PieceType::Knight));
+    tt.store(0xdead_beef, 7, Bound::Lower, -250, Some(piece_move), 3);
+    assert_eq!(
+        tt.probe(0xdead_beef, 5),
+        Some(TtEntry {
+            depth: 7,
+            bound: Bound::Lower,
+            score: -250,
+            best_move: Some(piece_move),
+        })
+    );
+    assert_eq!(tt.probe(0xdead_bee0, 5), None);
+    // an upper bound keeps the move the entry already had
+    tt.store(0xdead_beef, 8, Bound::Upper, -300, None, 3);
+    assert_eq!(
+        tt.probe(0xdead_beef, 3).unwrap().best_move,
+        Some(piece_move)
+    );
+    // mate in 3 plies from ply 4 is mate in 5 plies from ply 2
+    tt.store(42, 3, Bound::Exact, MATE - 7, None, 4);
+    assert_eq!(tt.probe(42, 2).unwrap().score, MATE - 5);
+    tt.store(43, 3, Bound::Exact, -MATE + 7, None, 4);
+    assert_eq!(tt.probe(43, 2).unwrap().score, -MATE + 5);
+    tt.clear();
+    assert_eq!(tt.probe(42, 2), None);
+    assert_eq!(tt.hashfull(), 0);
+}
+#[test]
+fn tt_replaces_stale_entries() {
+    let tt = TranspositionTable::new(1);
+    // small keys all land in the first bucket
+    let keys = [1, 2, 3, 4, 5];
+    for (i, &key) in keys[..4].iter().enumerate() {
+        tt.store(key, 10 + i, Bound::Exact, 0, None, 0);
+    }
+    tt.new_search();
+    tt.store(keys[4], 1, Bound::Exact, 0, None, 0);
+    assert!(tt.probe(keys[4], 0).is_some());
+    // the shallowest old entry made room
+    assert!(tt.probe(keys[0], 0).is_none());
+    assert!(keys[1..4].iter().all(|&key| tt.probe(key, 0).is_some()));
+}
@@ -0,0 +1,258 @@
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::color::Color;
+use crate::moves::Move;
+use crate::search::{mate_in, Limits, Search};
+use crate::tt::{TranspositionTable, DEFAULT_MB, MAX_MB, MIN_MB};
+
+// moves left in the game when the GUI doesn't say
+const MOVES_TO_GO: u64 = 30;
+// held back from every clock to cover GUI and pipe latency
+const OVERHEAD: u64 = 50;
+
+pub struct Uci {
+    board: Board,
+    stop: Arc<AtomicBool>,
+    tt: Arc<TranspositionTable>,
+    search: Option<JoinHandle<()>>,
+}
+impl Default for Uci {
+    fn default() -> Self {
+        Uci {
+            board: Board::default(),
+            stop: Arc::new(AtomicBool::new(false)),
+            tt: Arc::new(TranspositionTable::new(DEFAULT_MB)),
+            search: None,
+        }
+    }
+}
+impl Uci {
+    // false once the GUI asked us to quit
+    pub fn command(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("uci") => {
+                println!("id name bitboard_generator");
+                println!("id author bitboard_generator developers");
+                println!(
+                    "option name Hash type spin default {} min {} max {}",
+                    DEFAULT_MB, MIN_MB, MAX_MB
+                );
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => {
+                self.stop_search();
+                self.board = Board::default();
+                self.tt.clear();
+            }
+            Some("setoption") => {
+                self.stop_search();
+                if let Some(mb) = parse_hash(line) {
+                    self.tt = Arc::new(TranspositionTable::new(mb));
+                }
+            }
+            Some("position") => {
+                self.stop_search();
+                match parse_position(line) {
+                    Some(board) => self.board = board,
+                    None => println!("info string invalid position {}", line),
+                }
+            }
+            Some("go") => {
+                self.stop_search();
+                let (limits, infinite) = parse_go(line, self.board.side_to_move);
+                self.go(limits, infinite);
+            }
+            Some("stop") => self.stop_search(),
+            Some("quit") => {
+                self.stop_search();
+                return false;
+            }
+            _ => {}
+        }
+        true
+    }
+    fn go(&mut self, limits: Limits, infinite: bool) {
+        let board = self.board.clone();
+        let stop = Arc::clone(&self.stop);
+        let tt = Arc::clone(&self.tt);
+        stop.store(false, Ordering::Relaxed);
+        self.search = Some(thread::spawn(move || {
+            let mut search = Search::new(board, limits, &stop, &tt);
+            let result = search.run(|info| {
+                let millis = info.time.as_millis().max(1) as u64;
+                let score = match mate_in(info.score) {
+                    Some(moves) => format!("mate {}", moves),
+                    None => format!("cp {}", info.score),
+                };
+                let pv: Vec<String> = info.pv.iter().map(Move::to_uci).collect();
+                println!(
+                    "info depth {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+                    info.depth,
+                    score,
+                    info.nodes,
+                    info.nodes * 1000 / millis,
+                    info.hashfull,
+                    millis,
+                    pv.join(" ")
+                );
+            });
+            // an infinite search may only answer once it is told to stop
+            while infinite && !stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            match result {
+                Some(result) => println!("bestmove {}", result.best_move().to_uci()),
+                None => println!("bestmove {}", Move::NULL.to_uci()),
+            }
+        }));
+    }
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(search) = self.search.take() {
+            search.join().ok();
+        }
+    }
+}
+
+// megabytes for the table, out of range sizes are brought back into it
+pub fn parse_hash(line: &str) -> Option<usize> {
+    let mb = parse_option(line, "Hash")?.parse::<usize>().ok()?;
+    Some(mb.clamp(MIN_MB, MAX_MB))
+}
+// the value of "setoption name <name> value <value>" if it names `name`
+pub fn parse_option<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line
+        .trim()
+        .strip_prefix("setoption")?
+        .trim()
+        .strip_prefix("name")?;
+    let (option, value) = rest.split_once(" value ")?;
+    option
+        .trim()
+        .eq_ignore_ascii_case(name)
+        .then(|| value.trim())
+}
+
+// "position startpos|fen <fen> [moves ...]", None if the fen or a move is bad
+pub fn parse_position(line: &str) -> Option<Board> {
+    let (setup, moves) = match line.split_once(" moves ") {
+        Some((setup, moves)) => (setup, moves),
+        None => (line, ""),
+    };
+    let setup = setup.trim().strip_prefix("position")?.trim();
+    let mut board = match setup.strip_prefix("fen") {
+        Some(fen) => fen.parse::<Board>().ok()?,
+        None if setup == "startpos" => Board::default(),
+        None => return None,
+    };
+    for text in moves.split_whitespace() {
+        let piece_move = board.parse_uci_move(text).ok()?;
+        board.make_move(piece_move);
+    }
+    Some(board)
+}
+
+// the search limits and whether the search is infinite
+pub fn parse_go(line: &str, side_to_move: Color) -> (Limits, bool) {
+    let mut limits = Limits::default();
+    let mut infinite = false;
+    let (mut time, mut increment, mut moves_to_go) = (None, 0, MOVES_TO_GO);
+    let mut words = line.split_whitespace().skip(1);
+    while let Some(word) = words.next() {
+        let mut value = || {
+            words
+                .next()
+                .and_then(|value| value.parse::<i64>().ok())
+                .unwrap_or(0)
+                .max(0) as u64
+        };
+        match (word, side_to_move) {
+            ("depth", _) => limits.depth = Some(value() as usize),
+            ("nodes", _) => limits.nodes = Some(value()),
+            ("movetime", _) => limits.movetime = Some(Duration::from_millis(value())),
+            ("wtime", Color::White) | ("btime", Color::Black) => time = Some(value()),
+            ("winc", Color::White) | ("binc", Color::Black) => increment = value(),
+            ("movestogo", _) => moves_to_go = value().max(1),
+            ("wtime" | "btime" | "winc" | "binc", _) => {
+                value();
+            }
+            ("infinite", _) => infinite = true,
+            _ => {}
+        }
+    }
+    if let (Some(time), None) = (time, limits.movetime) {
+        let budget = time / moves_to_go + increment * 3 / 4;
+        let budget = budget.min(time.saturating_sub(OVERHEAD)).max(1);
+        limits.movetime = Some(Duration::from_millis(budget));
+    }
+    (limits, infinite)
+}
+
+pub fn run() {
+    let mut uci = Uci::default();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if !uci.command(&line) {
+            return;
+        }
+    }
+    uci.stop_search();
+}
+
+#[test]
+fn uci_position() {
+    let board = parse_position("position startpos moves e2e4 e7e5 g1f3").unwrap();
+    assert_eq!(
+        board.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+    let board = parse_position("position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves e1g1 e8c8")
+        .unwrap();
+    assert_eq!(board.to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+    assert!(parse_position("position startpos moves e2e5").is_none());
+    assert!(parse_position("position fen 8/8/8 w - - 0 1").is_none());
+}
+#[test]
+fn uci_go() {
+    let (limits, infinite) = parse_go("go depth 6 nodes 1000", Color::White);
+    assert_eq!(limits.depth, Some(6));
+    assert_eq!(limits.nodes, Some(1000));
+    assert!(!infinite);
+    let (limits, _) = parse_go("go wtime 60000 btime 1000 winc 1000 binc 0", Color::Black);
+    assert_eq!(limits.movetime, Some(Duration::from_millis(33)));
+    let (limits, _) = parse_go(
+        "go wtime 60000 btime 1000 winc 1000 movestogo 10",
+        Color::White,
+    );
+    assert_eq!(limits.movetime, Some(Duration::from_millis(6750)));
+    let (limits, infinite) = parse_go("go infinite", Color::White);
+    assert_eq!(limits, Limits::default());
+    assert!(infinite);
+}
+#[test]
+fn uci_setoption() {
+    assert_eq!(
+        parse_option("setoption name Hash value 128", "Hash"),
+        Some("128")
+    );
+    assert_eq!(
+        parse_option("setoption name hash value 1", "Hash"),
+        Some("1")
+    );
+    assert_eq!(parse_option("setoption name Threads value 2", "Hash"), None);
+    assert_eq!(parse_option("setoption name Hash", "Hash"), None);
+    assert_eq!(parse_hash("setoption name Hash value 256"), Some(256));
+    assert_eq!(parse_hash("setoption name Hash value 0"), Some(MIN_MB));
+    assert_eq!(
+        parse_hash("setoption name Hash value 18446744073709551615"),
+        Some(MAX_MB)
+    );
+    assert_eq!(parse_hash("setoption name Hash value -5"), None);
+}
@@ -0,0 +1,165 @@
+use crate::{board::Board, color::Color, PieceType};
+
+pub struct Zobrist {
+    pub pieces: [[[u64; 64]; 6]; 2],
+    pub side: u64,
+    // keyed by the castling rook's square
+    pub castling: [u64; 64],
+    pub en_pessant: [u64; 8],
+}
+
+const fn xorshift(mut seed: u64) -> u64 {
+    seed ^= seed >> 12;
+    seed ^= seed << 25;
+    seed ^= seed >> 27;
+    seed
+}
+
+impl Zobrist {
+    const fn new(mut seed: u64) -> Self {
+        let mut zobrist = Zobrist {
+            pieces: [[[0; 64]; 6]; 2],
+            side: 0,
+            castling: [0; 64],
+            en_pessant: [0; 8],
+        };
+        let mut i = 0;
+        while i < 2 * 6 * 64 {
+            seed = xorshift(seed);
+            zobrist.pieces[i / 384][i / 64 % 6][i % 64] = seed.wrapping_mul(0x2545f4914f6cdd1d);
+            i += 1;
+        }
+        i = 0;
+        while i < 64 {
+            seed = xorshift(seed);
+            zobrist.castling[i] = seed.wrapping_mul(0x2545f4914f6cdd1d);
+            i += 1;
+        }
+        i = 0;
+        while i < 8 {
+            seed = xorshift(seed);
+            zobrist.en_pessant[i] = seed.wrapping_mul(0x2545f4914f6cdd1d);
+            i += 1;
+        }
+        seed = xorshift(seed);
+        zobrist.side = seed.wrapping_mul(0x2545f4914f6cdd1d);
+        zobrist
+    }
+    pub const fn piece(&self, color: Color, piece_type: PieceType, index: usize) -> u64 {
+        self.pieces[color as usize][piece_type as usize][index]
+    }
+}
+
+pub static ZOBRIST: Zobrist = Zobrist::new(0x3243f6a8885a308d);
+
+impl Board {
+    pub const fn hash(&self) -> u64 {
+        self.hash
+    }
+    // castling rights and a capturable en passant square, everything the pieces don't cover
+    pub fn state_hash(&self) -> u64 {
+        let mut hash = 0;
+        let (queenside, kingside, black_queenside, black_kingside) = self.state.castling;
+        for rook in [queenside, kingside, black_queenside, black_kingside]
+            .into_iter()
+            .flatten()
+        {
+            hash ^= ZOBRIST.castling[rook];
+        }
+        // an en passant square nobody can take on is the same position as none at all
+        if let Some(en_pessant) = self.state.en_pessant {
+            let capturers = self.pawn_attacks(en_pessant, !self.side_to_move).0
+                & self.pieces.pawn.0
+                & self.colors(self.side_to_move).0;
+            if capturers > 0 {
+                hash ^= ZOBRIST.en_pessant[en_pessant & 7];
+            }
+        }
+        hash
+    }
+    pub fn compute_pawn_hash(&self) -> u64 {
+        let mut hash = 0;
+        for (color, pawns) in [
+            (Color::White, self.pieces.pawn.0 & self.pieces.white.0),
+            (Color::Black, self.pieces.pawn.0 & self.pieces.black.0),
+        ] {
+            let mut pawns = pawns;
+            while pawns > 0 {
+                hash ^= ZOBRIST.piece(color, PieceType::Pawn, pawns.trailing_zeros() as usize);
+                pawns &= pawns - 1;
+            }
+        }
+        hash
+    }
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = self.state_hash();
+        for index in 0..64 {
+            if let Some(piece_type) = self.piece_on(index) {
+                let color = match self.pieces.white.0 & 1u64 << index {
+                    0 => Color::Black,
+                    _ => Color::White,
+                };
+                hash ^= ZOBRIST.piece(color, piece_type, index);
+            }
+        }
+        if self.side_to_move == Color::Black {
+            hash ^= ZOBRIST.side;
+        }
+        hash
+    }
+}
+
+#[test]
+fn incremental_hash_matches() {
+    let mut seed = 0x2545f4914f6cdd1du64;
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9",
+    ] {
+        let start = fen.parse::<Board>().unwrap();
+        assert_eq!(start.hash(), start.compute_hash());
+        for _ in 0..20 {
+            let mut board = start.clone();
+            let mut history = vec![];
+            for _ in 0..100 {
+                let moves = board.legal_moves();
+                if moves.is_empty() {
+                    break;
+                }
+                seed = xorshift(seed);
+                let piece_move = moves[seed as usize % moves.len()];
+                history.push((piece_move, board.make_move(piece_move)));
+                assert_eq!(board.hash(), board.compute_hash(), "{}", board);
+                assert_eq!(board.pawn_hash, board.compute_pawn_hash(), "{}", board);
+            }
+            while let Some((piece_move, undo)) = history.pop() {
+                board.unmake_move(piece_move, undo);
+                assert_eq!(board.hash(), board.compute_hash(), "{}", board);
+                assert_eq!(board.pawn_hash, board.compute_pawn_hash(), "{}", board);
+            }
+        }
+    }
+}
+#[test]
+fn hash_ignores_move_order_and_dead_en_pessant() {
+    use crate::moves::Move;
+    let mut board = Board::default();
+    let mut other = Board::default();
+    for piece_move in [(6, 21), (57, 42), (1, 18), (62, 45)] {
+        board.make_move(Move::new(piece_move.0, piece_move.1, None));
+    }
+    for piece_move in [(1, 18), (62, 45), (6, 21), (57, 42)] {
+        other.make_move(Move::new(piece_move.0, piece_move.1, None));
+    }
+    assert_eq!(board.hash(), other.hash());
+    // e2e4 sets an en passant square no black pawn can use
+    let mut board = Board::default();
+    board.make_move(Move::new(12, 28, None));
+    let without = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        .parse::<Board>()
+        .unwrap();
+    assert_eq!(board.hash(), without.hash());
+    assert_ne!(board.hash(), Board::default().hash());
+}